@@ -361,14 +361,19 @@ program
 
 program
   .command("get-payments-delegate-pda")
-  .description("Get the payments delegate PDA")
-  .action(() => {
+  .description("Get the payments delegate PDA of a user payment")
+  .requiredOption("-u, --user <pubkey>", "User public key")
+  .requiredOption("-t, --token-mint <pubkey>", "Token mint public key")
+  .action((options) => {
     try {
       const sdk = createSDK(
         program.opts().connectionUrl,
         program.opts().keypath
       );
-      const pda = sdk.getPaymentsDelegatePda();
+      const user = new PublicKey(options.user);
+      const tokenMint = new PublicKey(options.tokenMint);
+      const userPayment = sdk.getUserPaymentPda(user, tokenMint);
+      const pda = sdk.getPaymentsDelegatePda(userPayment.address);
       console.log("Payments Delegate PDA:", pda.address.toString());
       console.log("Bump:", pda.bump);
     } catch (error) {
@@ -377,6 +382,24 @@ program
     }
   });
 
+program
+  .command("get-legacy-payments-delegate-pda")
+  .description("Get the legacy program-wide payments delegate PDA")
+  .action(() => {
+    try {
+      const sdk = createSDK(
+        program.opts().connectionUrl,
+        program.opts().keypath
+      );
+      const pda = sdk.getLegacyPaymentsDelegatePda();
+      console.log("Legacy Payments Delegate PDA:", pda.address.toString());
+      console.log("Bump:", pda.bump);
+    } catch (error) {
+      console.error("Error getting legacy payments delegate PDA:", error);
+      process.exit(1);
+    }
+  });
+
 program
   .command("list-user-payments")
   .description("List all user payment")
@@ -0,0 +1,125 @@
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)]
+
+//! A minimal transfer hook that counts the transfers of a mint. It exists so
+//! the integration tests can exercise transfer-hook mints in `execute_payment`.
+
+use anchor_lang::prelude::*;
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
+};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("8CQSJERxYpmP8FLDP9S9CfmH4Q6Ppzbv19fN6ZFgYN3E");
+
+pub const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+pub const COUNTER_SEED: &[u8] = b"counter";
+
+#[program]
+pub mod dummy_transfer_hook {
+    use super::*;
+
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        let extra_account_metas = extra_account_metas()?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas,
+        )?;
+        Ok(())
+    }
+
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, _amount: u64) -> Result<()> {
+        let counter = &mut ctx.accounts.counter;
+        counter.transfers = counter.transfers.checked_add(1).unwrap();
+        Ok(())
+    }
+}
+
+/// The hook requires a single extra account: the per-mint transfer counter
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    Ok(vec![ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: COUNTER_SEED.to_vec(),
+            },
+            // index 1 is the mint in the execute instruction
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?])
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Initialized as an extra account meta list by the handler
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// CHECK: The Token-2022 mint that uses this hook
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TransferCounter::SIZE,
+        seeds = [COUNTER_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub counter: Account<'info, TransferCounter>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Account order is fixed by the transfer hook interface
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    /// CHECK: Source token account
+    pub source_token: UncheckedAccount<'info>,
+
+    /// CHECK: The mint being transferred
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Destination token account
+    pub destination_token: UncheckedAccount<'info>,
+
+    /// CHECK: Owner or delegate of the source account
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by seeds
+    #[account(
+        seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [COUNTER_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub counter: Account<'info, TransferCounter>,
+}
+
+#[account]
+pub struct TransferCounter {
+    pub transfers: u64,
+}
+
+impl TransferCounter {
+    pub const SIZE: usize = 8 + // discriminator
+        8; // transfers: u64
+}
@@ -8,3 +8,14 @@ pub const USER_PAYMENT_SEED: &[u8] = b"user_payment";
 pub const GATEWAY_SEED: &[u8] = b"gateway";
 pub const PAYMENT_POLICY_SEED: &[u8] = b"payment_policy";
 pub const PAYMENTS_SEED: &[u8] = b"payments";
+
+/// Basis points denominator (100%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+/// Upper bound for the protocol fee the admin can configure (10%)
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+/// Default upper bound for gateway fees set on initialize (10%)
+pub const DEFAULT_MAX_GATEWAY_FEE_BPS: u16 = 1_000;
+/// Failed retries tolerated before a past due policy is suspended
+pub const DEFAULT_MAX_PAYMENT_RETRIES: u8 = 3;
+/// Time after the first failed payment before a past due policy is suspended (7 days)
+pub const DEFAULT_RETRY_WINDOW_SECONDS: i64 = 604_800;
@@ -28,4 +28,50 @@ pub enum RecurringPaymentsError {
     PolicyPaused,
     #[msg("Invalid Interval")]
     InvalidInterval,
+    #[msg("Fee basis points out of range")]
+    InvalidFeeBps,
+    #[msg("Invalid maximum policies per user")]
+    InvalidMaxPolicies,
+    #[msg("No admin transfer is pending")]
+    NoPendingAdmin,
+    #[msg("Gateway fee exceeds the configured maximum")]
+    GatewayFeeTooHigh,
+    #[msg("Combined fees exceed the payment amount")]
+    FeesExceedAmount,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Payment gateway is not active")]
+    GatewayInactive,
+    #[msg("Payment gateway is retired")]
+    GatewayRetired,
+    #[msg("Invalid successor gateway")]
+    InvalidSuccessorGateway,
+    #[msg("Payment policy allowance exhausted")]
+    PolicyAllowanceExceeded,
+    #[msg("End timestamp must be after the first payment")]
+    InvalidEndTimestamp,
+    #[msg("Invalid installment plan")]
+    InvalidInstallmentPlan,
+    #[msg("Invalid milestone")]
+    InvalidMilestone,
+    #[msg("Milestone has not been approved")]
+    MilestoneNotApproved,
+    #[msg("Usage amount is missing or not allowed for this policy")]
+    InvalidUsageAmount,
+    #[msg("Usage amount exceeds the maximum per period")]
+    UsageExceedsCap,
+    #[msg("Operation not supported for this policy type")]
+    InvalidPolicyType,
+    #[msg("Invalid stream")]
+    InvalidStream,
+    #[msg("Nothing to withdraw")]
+    NothingToWithdraw,
+    #[msg("Payment can still be collected")]
+    PaymentStillCollectable,
+    #[msg("Invalid dunning settings")]
+    InvalidDunningSettings,
+    #[msg("Payment policy has expired")]
+    PolicyExpired,
+    #[msg("Payment policy has not expired")]
+    PolicyNotExpired,
 }
@@ -0,0 +1,33 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.pending_admin != Pubkey::default() @ RecurringPaymentsError::NoPendingAdmin,
+        constraint = config.pending_admin == new_admin.key() @ RecurringPaymentsError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+pub fn handler_accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    let old_admin = config.admin;
+    config.admin = ctx.accounts.new_admin.key();
+    config.pending_admin = Pubkey::default();
+
+    emit!(AdminTransferred {
+        old_admin,
+        new_admin: config.admin,
+    });
+
+    msg!("Admin changed from {:?} to {:?}", old_admin, config.admin);
+
+    Ok(())
+}
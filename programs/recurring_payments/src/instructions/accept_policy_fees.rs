@@ -0,0 +1,73 @@
+use crate::{constants::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct AcceptPolicyFees<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+
+    #[account(
+        seeds = [GATEWAY_SEED, gateway.authority.as_ref()],
+        bump = gateway.bump,
+        constraint = gateway.key() == payment_policy.gateway,
+    )]
+    pub gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+pub fn handler_accept_policy_fees(ctx: Context<AcceptPolicyFees>, _policy_id: u32) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let gateway = &ctx.accounts.gateway;
+    let config = &ctx.accounts.config;
+    let clock = Clock::get()?;
+
+    let old_gateway_fee_bps = payment_policy.gateway_fee_bps;
+    let old_protocol_fee_bps = payment_policy.protocol_fee_bps;
+
+    payment_policy.snapshot_fees(gateway.gateway_fee_bps, config.protocol_fee_bps);
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(PolicyFeesAccepted {
+        payment_policy: payment_policy.key(),
+        gateway: gateway.key(),
+        old_gateway_fee_bps,
+        new_gateway_fee_bps: payment_policy.gateway_fee_bps,
+        old_protocol_fee_bps,
+        new_protocol_fee_bps: payment_policy.protocol_fee_bps,
+    });
+
+    msg!(
+        "Fees accepted for policy ID: {}, gateway fee: {} bps, protocol fee: {} bps",
+        payment_policy.policy_id,
+        payment_policy.gateway_fee_bps,
+        payment_policy.protocol_fee_bps
+    );
+
+    Ok(())
+}
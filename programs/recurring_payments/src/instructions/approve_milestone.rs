@@ -0,0 +1,68 @@
+use crate::{constants::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct ApproveMilestone<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+}
+
+pub fn handler_approve_milestone(
+    ctx: Context<ApproveMilestone>,
+    _policy_id: u32,
+    milestone_index: u8,
+) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let clock = Clock::get()?;
+
+    // Milestones are approved one at a time and in order
+    let milestone_count = match &payment_policy.policy_type {
+        PolicyType::Milestone { milestones, .. } => PolicyType::milestone_count(milestones),
+        _ => return err!(crate::error::RecurringPaymentsError::InvalidMilestone),
+    };
+    require!(
+        milestone_index
+            == payment_policy
+                .milestones_approved
+                .max(payment_policy.milestone_cursor)
+            && milestone_index < milestone_count,
+        crate::error::RecurringPaymentsError::InvalidMilestone
+    );
+
+    payment_policy.milestones_approved = milestone_index + 1;
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(MilestoneApproved {
+        payment_policy: payment_policy.key(),
+        milestone_index,
+    });
+
+    msg!(
+        "Milestone {} approved for policy ID: {}",
+        milestone_index,
+        payment_policy.policy_id
+    );
+
+    Ok(())
+}
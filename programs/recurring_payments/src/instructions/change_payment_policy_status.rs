@@ -8,6 +8,7 @@ pub struct ChangePaymentPolicyStatus<'info> {
     pub owner: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
         bump = user_payment.bump,
         constraint = user_payment.owner == owner.key(),
@@ -27,6 +28,12 @@ pub struct ChangePaymentPolicyStatus<'info> {
         bump = payment_policy.bump,
     )]
     pub payment_policy: Account<'info, PaymentPolicy>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
 }
 
 pub fn handler_change_payment_policy_status(
@@ -38,8 +45,41 @@ pub fn handler_change_payment_policy_status(
     let user_payment = &mut ctx.accounts.user_payment;
     let clock = Clock::get()?;
 
-    // Update the policy status
+    // Only transitions allowed by the status state machine, finished policies stay finished
     let old_status = payment_policy.status.clone();
+    require!(
+        old_status.can_transition_to(&new_status),
+        crate::error::RecurringPaymentsError::InvalidPolicyStatusTransition
+    );
+
+    // Pausing would not stop a stream from accruing and cancelling would strand what
+    // accrued, streams are ended with stop_stream and a final withdrawal
+    require!(
+        !(matches!(new_status, PaymentStatus::Paused | PaymentStatus::Cancelled)
+            && payment_policy.is_open_stream()),
+        crate::error::RecurringPaymentsError::InvalidPolicyType
+    );
+
+    // A subscription that used up its term without auto-renew cannot be resumed
+    require!(
+        !(new_status.is_active() && payment_policy.subscription_finished()),
+        crate::error::RecurringPaymentsError::InvalidPolicyStatusTransition
+    );
+
+    // Resuming a suspended policy starts a fresh dunning cycle
+    if old_status == PaymentStatus::Suspended {
+        payment_policy.failed_attempts = 0;
+        payment_policy.first_failed_at = 0;
+    }
+
+    // Keep the active policy count in sync, resuming counts against the cap again
+    if !old_status.is_active() && new_status.is_active() {
+        user_payment.add_active_policy(ctx.accounts.config.max_policies_per_user)?;
+    } else if old_status.is_active() && !new_status.is_active() {
+        user_payment.remove_active_policy();
+    }
+
+    // Update the policy status
     payment_policy.status = new_status.clone();
     payment_policy.updated_at = clock.unix_timestamp;
 
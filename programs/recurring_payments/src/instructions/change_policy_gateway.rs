@@ -0,0 +1,75 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct ChangePolicyGateway<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+
+    #[account(
+        seeds = [GATEWAY_SEED, new_gateway.authority.as_ref()],
+        bump = new_gateway.bump,
+        constraint = new_gateway.is_active @ RecurringPaymentsError::GatewayInactive,
+    )]
+    pub new_gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+pub fn handler_change_policy_gateway(
+    ctx: Context<ChangePolicyGateway>,
+    _policy_id: u32,
+) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let new_gateway = &ctx.accounts.new_gateway;
+    let clock = Clock::get()?;
+
+    let old_gateway = payment_policy.gateway;
+    payment_policy.gateway = new_gateway.key();
+    // Signing the move is the subscriber's consent to the new gateway's fees
+    payment_policy.snapshot_fees(
+        new_gateway.gateway_fee_bps,
+        ctx.accounts.config.protocol_fee_bps,
+    );
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(PaymentPolicyGatewayChanged {
+        payment_policy: payment_policy.key(),
+        old_gateway,
+        new_gateway: payment_policy.gateway,
+    });
+
+    msg!(
+        "Payment policy ID: {} moved from gateway {:?} to {:?}",
+        payment_policy.policy_id,
+        old_gateway,
+        payment_policy.gateway
+    );
+
+    Ok(())
+}
@@ -37,6 +37,8 @@ pub fn handler_create_payment_gateway(
     name: [u8; 32],
     url: [u8; 64],
 ) -> Result<()> {
+    ctx.accounts.config.validate_gateway_fee(gateway_fee_bps)?;
+
     let gateway = &mut ctx.accounts.gateway;
     let clock = Clock::get()?;
 
@@ -50,6 +52,10 @@ pub fn handler_create_payment_gateway(
     gateway.name = name;
     gateway.url = url;
     gateway.signer = ctx.accounts.authority.key();
+    gateway.suspended_by_admin = false;
+    gateway.successor = Pubkey::default();
+    gateway.max_payment_retries = DEFAULT_MAX_PAYMENT_RETRIES;
+    gateway.retry_window_seconds = DEFAULT_RETRY_WINDOW_SECONDS;
 
     emit!(PaymentGatewayCreated {
         authority: gateway.authority,
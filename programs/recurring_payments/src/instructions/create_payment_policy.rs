@@ -1,4 +1,4 @@
-use crate::{constants::*, state::*};
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -24,7 +24,7 @@ pub struct CreatePaymentPolicy<'info> {
     #[account(
         seeds = [GATEWAY_SEED, gateway.authority.as_ref()],
         bump = gateway.bump,
-        constraint = gateway.is_active,
+        constraint = gateway.is_active @ RecurringPaymentsError::GatewayInactive,
     )]
     pub gateway: Account<'info, PaymentGateway>,
 
@@ -41,6 +41,12 @@ pub struct CreatePaymentPolicy<'info> {
     )]
     pub payment_policy: Account<'info, PaymentPolicy>,
 
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -57,6 +63,14 @@ pub fn handler_create_payment_policy(
     let user_payment = &mut ctx.accounts.user_payment;
     let clock = Clock::get()?;
 
+    // Streams cannot accrue for time before they were created
+    if let PolicyType::Stream { start_time, .. } = &policy_type {
+        require!(
+            *start_time >= clock.unix_timestamp,
+            crate::error::RecurringPaymentsError::InvalidStream
+        );
+    }
+
     payment_policy.user_payment = user_payment.key();
     payment_policy.recipient = ctx.accounts.recipient.key();
     payment_policy.gateway = ctx.accounts.gateway.key();
@@ -69,9 +83,26 @@ pub fn handler_create_payment_policy(
     payment_policy.updated_at = clock.unix_timestamp;
     payment_policy.policy_id = policy_id;
     payment_policy.bump = ctx.bumps.payment_policy;
+    payment_policy.allowance_enabled = false;
+    payment_policy.allowance_remaining = 0;
+    payment_policy.milestone_cursor = 0;
+    payment_policy.milestone_approval_required = false;
+    payment_policy.milestones_approved = 0;
+    payment_policy.catch_up = false;
+    payment_policy.periods_due = 0;
+    payment_policy.arrears_periods = 0;
+    payment_policy.failed_attempts = 0;
+    payment_policy.first_failed_at = 0;
+    payment_policy.term_start_payment_count = 0;
+    payment_policy.renewal_count = 0;
+    payment_policy.snapshot_fees(
+        ctx.accounts.gateway.gateway_fee_bps,
+        ctx.accounts.config.protocol_fee_bps,
+    );
 
     // Update user payment account
-    user_payment.active_policies_count = user_payment.active_policies_count.checked_add(1).unwrap();
+    user_payment.add_active_policy(ctx.accounts.config.max_policies_per_user)?;
+    user_payment.last_policy_id = user_payment.last_policy_id.max(policy_id);
     user_payment.updated_at = clock.unix_timestamp;
 
     emit!(PaymentPolicyCreated {
@@ -83,12 +114,8 @@ pub fn handler_create_payment_policy(
         memo: payment_policy.memo,
     });
 
-    // Get next_payment_due from policy_type
-    let next_payment_due = match &policy_type {
-        PolicyType::Subscription {
-            next_payment_due, ..
-        } => *next_payment_due,
-    };
+    // Get next_payment_due from the policy
+    let next_payment_due = payment_policy.next_payment_due();
 
     msg!(
         "Payment policy created with ID: {}, recipient: {:?}, next payment due: {}",
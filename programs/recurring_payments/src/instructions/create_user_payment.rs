@@ -1,6 +1,6 @@
 use crate::{error::RecurringPaymentsError, state::*, USER_PAYMENT_SEED};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount};
 
 #[derive(Accounts)]
 pub struct CreateUserPayment<'info> {
@@ -20,8 +20,8 @@ pub struct CreateUserPayment<'info> {
         constraint = token_account.owner == owner.key(),
         constraint = token_account.mint == token_mint.key()
     )]
-    pub token_account: Account<'info, TokenAccount>,
-    pub token_mint: Account<'info, Mint>,
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         seeds = [b"config"],
@@ -45,6 +45,7 @@ pub fn handler_create_user_payment(ctx: Context<CreateUserPayment>) -> Result<()
     user_payment.updated_at = clock.unix_timestamp;
     user_payment.is_active = true;
     user_payment.bump = ctx.bumps.user_payment;
+    user_payment.last_policy_id = 0;
 
     emit!(UserPaymentCreated {
         owner: user_payment.owner,
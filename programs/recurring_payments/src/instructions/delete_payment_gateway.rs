@@ -1,4 +1,4 @@
-use crate::{constants::*, state::*};
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -13,6 +13,8 @@ pub struct DeletePaymentGateway<'info> {
         mut,
         seeds = [GATEWAY_SEED, authority.key().as_ref()],
         bump = gateway.bump,
+        // A retired gateway holds the successor its policies are rebound to
+        constraint = !gateway.is_retired() @ RecurringPaymentsError::GatewayRetired,
         close = admin
     )]
     pub gateway: Account<'info, PaymentGateway>,
@@ -26,6 +26,7 @@ pub struct DeletePaymentPolicy<'info> {
             policy_id.to_le_bytes().as_ref()
         ],
         bump = payment_policy.bump,
+        constraint = !payment_policy.is_open_stream() @ crate::error::RecurringPaymentsError::InvalidPolicyType,
         close = owner
     )]
     pub payment_policy: Account<'info, PaymentPolicy>,
@@ -45,11 +46,10 @@ pub fn handler_delete_payment_policy(
         policy_id: payment_policy.policy_id,
     });
 
-    // Update user payment count (decrease active policies count)
-    user_payment.active_policies_count = user_payment
-        .active_policies_count
-        .checked_sub(1)
-        .unwrap_or(0);
+    // Update user payment count (paused policies have already been discounted)
+    if payment_policy.status.is_active() {
+        user_payment.remove_active_policy();
+    }
     user_payment.updated_at = clock.unix_timestamp;
 
     msg!(
@@ -1,6 +1,10 @@
-use crate::{constants::*, state::*, utils::calculate_next_payment_due};
+use crate::{
+    instructions::policy_payment::*,
+    state::*,
+    utils::{calculate_next_payment_due, next_period_due},
+};
 use anchor_lang::{prelude::*, solana_program::program_option::COption};
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::TokenAccount;
 
 // Add this helper function to your program
 pub fn token_account_has_delegate(
@@ -19,84 +23,72 @@ pub struct ExecutePayment<'info> {
     pub fee_payer: Signer<'info>,
 
     #[account(
-        seeds = [PAYMENTS_SEED],
-        bump
+        constraint = payment.gateway.signer == fee_payer.key() || payment.user_payment.owner == fee_payer.key(),
     )]
-    /// CHECK: Program-derived delegate authority for token transfers
-    pub payments_delegate: UncheckedAccount<'info>,
-
-    #[account(
-        mut,
-        seeds = [PAYMENT_POLICY_SEED, payment_policy.user_payment.as_ref(), payment_policy.policy_id.to_le_bytes().as_ref()],
-        bump = payment_policy.bump,
-        constraint = payment_policy.status == PaymentStatus::Active @ crate::error::RecurringPaymentsError::PolicyPaused,
-    )]
-    pub payment_policy: Box<Account<'info, PaymentPolicy>>,
-
-    #[account(
-        mut,
-        seeds = [USER_PAYMENT_SEED, user_payment.owner.as_ref(), user_payment.token_mint.as_ref()],
-        bump = user_payment.bump,
-        constraint = user_payment.is_active,
-    )]
-    pub user_payment: Box<Account<'info, UserPayment>>,
-
-    #[account(
-        mut,
-        seeds = [GATEWAY_SEED, gateway.authority.as_ref()],
-        bump = gateway.bump,
-        constraint = gateway.is_active,
-        constraint = gateway.key() == payment_policy.gateway,
-        constraint = gateway.signer == fee_payer.key() || user_payment.owner == fee_payer.key(),
-    )]
-    pub gateway: Box<Account<'info, PaymentGateway>>,
-
-    #[account(
-        seeds = [CONFIG_SEED],
-        bump = config.bump,
-        constraint = !config.emergency_pause,
-    )]
-    pub config: Box<Account<'info, ProgramConfig>>,
-
-    #[account(
-        mut,
-        constraint = user_token_account.key() == user_payment.token_account,
-        constraint = user_token_account.mint == user_payment.token_mint,
-        constraint = token_account_has_delegate(&user_token_account, &payments_delegate.key()) @ crate::error::RecurringPaymentsError::NoDelegateSet,
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub payment: PolicyPayment<'info>,
+}
 
-    #[account(
-        mut,
-        constraint = recipient_token_account.mint == user_payment.token_mint,
-        constraint = recipient_token_account.owner == payment_policy.recipient,
-    )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+/// Move a policy to a terminal status
+pub fn end_payment_policy(
+    payment_policy: &mut Account<PaymentPolicy>,
+    user_payment: &mut Account<UserPayment>,
+    new_status: PaymentStatus,
+    timestamp: i64,
+) {
+    let old_status = payment_policy.status.clone();
+    if old_status.is_active() {
+        user_payment.remove_active_policy();
+    }
+    payment_policy.status = new_status.clone();
+    payment_policy.updated_at = timestamp;
 
-    #[account(
-        mut,
-        constraint = gateway_fee_account.mint == user_payment.token_mint,
-        constraint = gateway_fee_account.owner == gateway.fee_recipient,
-    )]
-    pub gateway_fee_account: Account<'info, TokenAccount>,
+    emit!(PaymentPolicyStatusChanged {
+        payment_policy: payment_policy.key(),
+        old_status,
+        new_status,
+    });
+}
 
-    #[account(
-        mut,
-        constraint = protocol_fee_account.mint == user_payment.token_mint,
-        constraint = protocol_fee_account.owner == config.fee_recipient,
-    )]
-    pub protocol_fee_account: Account<'info, TokenAccount>,
+/// Start a new term of an auto-renewing subscription
+fn renew_subscription_term(payment_policy: &mut Account<PaymentPolicy>, timestamp: i64) {
+    payment_policy.term_start_payment_count = payment_policy.payment_count;
+    payment_policy.renewal_count = payment_policy.renewal_count.saturating_add(1);
 
-    pub token_program: Program<'info, Token>,
+    emit!(SubscriptionRenewed {
+        payment_policy: payment_policy.key(),
+        renewal_count: payment_policy.renewal_count,
+        next_payment_due: payment_policy.next_payment_due(),
+        timestamp,
+    });
 }
 
-pub fn handler_execute_payment(ctx: Context<ExecutePayment>) -> Result<()> {
-    let payment_policy = &mut ctx.accounts.payment_policy;
-    let user_payment = &mut ctx.accounts.user_payment;
-    let gateway = &mut ctx.accounts.gateway;
-    let config = &ctx.accounts.config;
+/// Mints with a transfer hook require the hook program, its extra account meta
+/// list and the resolved extra accounts to be passed as remaining accounts.
+///
+/// `usage_amount` is the usage reported by the gateway signer for metered
+/// policies and must be `None` for every other policy type.
+pub fn handler_execute_payment<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecutePayment<'info>>,
+    usage_amount: Option<u64>,
+) -> Result<()> {
+    let fee_payer = ctx.accounts.fee_payer.key();
+    let delegate_bump = ctx.bumps.payment.payments_delegate;
+    let payment = &mut ctx.accounts.payment;
     let clock = Clock::get()?;
 
+    // Only the gateway signer reports usage, and only for metered policies
+    let is_metered = matches!(
+        payment.payment_policy.policy_type,
+        PolicyType::Metered { .. }
+    );
+    require!(
+        is_metered == usage_amount.is_some()
+            && (!is_metered || payment.gateway.signer == fee_payer),
+        crate::error::RecurringPaymentsError::InvalidUsageAmount
+    );
+    let payment_policy = &mut payment.payment_policy;
+    let user_payment = &mut payment.user_payment;
+
     // Get payment details from policy
     let (payment_amount, current_next_due, payment_frequency) = match &payment_policy.policy_type {
         PolicyType::Subscription {
@@ -104,7 +96,53 @@ pub fn handler_execute_payment(ctx: Context<ExecutePayment>) -> Result<()> {
             next_payment_due,
             payment_frequency,
             ..
-        } => (*amount, *next_payment_due, payment_frequency),
+        } => (*amount, *next_payment_due, Some(payment_frequency.clone())),
+        PolicyType::Installment {
+            total_amount,
+            num_installments,
+            installment_amount,
+            period,
+            next_installment_due,
+            installments_completed,
+            ..
+        } => (
+            PolicyType::installment_due_amount(
+                *total_amount,
+                *num_installments,
+                *installment_amount,
+                *installments_completed,
+            ),
+            *next_installment_due,
+            Some(period.clone()),
+        ),
+        PolicyType::OneTime {
+            amount, due_date, ..
+        } => (*amount, *due_date, None),
+        PolicyType::Milestone { milestones, .. } => (
+            milestones
+                .get(payment_policy.milestone_cursor as usize)
+                .copied()
+                .unwrap_or(0),
+            payment_policy.next_payment_due(),
+            None,
+        ),
+        PolicyType::Metered {
+            max_amount_per_period,
+            payment_frequency,
+            next_payment_due,
+            ..
+        } => {
+            let usage = usage_amount.unwrap_or(0);
+            require!(
+                usage <= *max_amount_per_period,
+                crate::error::RecurringPaymentsError::UsageExceedsCap
+            );
+            (usage, *next_payment_due, Some(payment_frequency.clone()))
+        }
+        // Streams are paid out through withdraw_stream
+        PolicyType::Stream { .. } => {
+            return err!(crate::error::RecurringPaymentsError::InvalidPolicyType)
+        }
     };
 
     // Validate payment timing
@@ -113,76 +151,81 @@ pub fn handler_execute_payment(ctx: Context<ExecutePayment>) -> Result<()> {
         crate::error::RecurringPaymentsError::PaymentNotDue
     );
 
-    // Check if user has sufficient balance
+    // Check that the next milestone was approved when approval is required
     require!(
-        ctx.accounts.user_token_account.amount >= payment_amount,
-        crate::error::RecurringPaymentsError::InsufficientBalance
+        !payment_policy.milestone_awaiting_approval(),
+        crate::error::RecurringPaymentsError::MilestoneNotApproved
     );
 
-    // Calculate fees
-    let gateway_fee = payment_amount
-        .checked_mul(gateway.gateway_fee_bps as u64)
-        .unwrap()
-        .checked_div(10000)
-        .unwrap();
-
-    let protocol_fee = payment_amount
-        .checked_mul(config.protocol_fee_bps as u64)
-        .unwrap()
-        .checked_div(10000)
-        .unwrap();
-
-    let recipient_amount = payment_amount
-        .checked_sub(gateway_fee)
-        .unwrap()
-        .checked_sub(protocol_fee)
-        .unwrap();
+    // One-time payments cannot be collected after their grace period, they
+    // are moved to Expired by `expire_payment_policy`
+    require!(
+        !payment_policy.grace_period_elapsed(clock.unix_timestamp),
+        crate::error::RecurringPaymentsError::PolicyExpired
+    );
 
-    // Transfer to recipient
-    if recipient_amount > 0 {
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.recipient_token_account.to_account_info(),
-            authority: ctx.accounts.payments_delegate.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let seeds = &[PAYMENTS_SEED, &[ctx.bumps.payments_delegate]];
-        let signer_seeds = &[&seeds[..]];
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        token::transfer(cpi_ctx, recipient_amount)?;
+    // Complete subscriptions that reached their end date or lifetime cap
+    // instead of charging them
+    if payment_policy.subscription_limit_reached(payment_amount, clock.unix_timestamp) {
+        end_payment_policy(
+            payment_policy,
+            user_payment,
+            PaymentStatus::Completed,
+            clock.unix_timestamp,
+        );
+        msg!(
+            "Payment policy ID: {} completed without payment",
+            payment_policy.policy_id
+        );
+        return Ok(());
     }
 
-    // Transfer gateway fee
-    if gateway_fee > 0 {
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.gateway_fee_account.to_account_info(),
-            authority: ctx.accounts.payments_delegate.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let seeds = &[PAYMENTS_SEED, &[ctx.bumps.payments_delegate]];
-        let signer_seeds = &[&seeds[..]];
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        token::transfer(cpi_ctx, gateway_fee)?;
+    // Complete subscriptions whose term is used up without auto-renew, such as
+    // ones resumed after being paused at max renewals
+    if payment_policy.subscription_finished() {
+        end_payment_policy(
+            payment_policy,
+            user_payment,
+            PaymentStatus::Completed,
+            clock.unix_timestamp,
+        );
+        msg!(
+            "Payment policy ID: {} completed without payment",
+            payment_policy.policy_id
+        );
+        return Ok(());
     }
 
-    // Transfer protocol fee
-    if protocol_fee > 0 {
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.protocol_fee_account.to_account_info(),
-            authority: ctx.accounts.payments_delegate.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let seeds = &[PAYMENTS_SEED, &[ctx.bumps.payments_delegate]];
-        let signer_seeds = &[&seeds[..]];
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        token::transfer(cpi_ctx, protocol_fee)?;
+    // An auto-renewing subscription resumed at the end of its term starts the next one
+    if payment_policy.subscription_term_ended() {
+        renew_subscription_term(payment_policy, clock.unix_timestamp);
     }
 
-    // Calculate next payment due time based on payment frequency
-    let new_next_due =
-        calculate_next_payment_due(current_next_due, payment_frequency, clock.unix_timestamp)?;
+    let collected = payment.collect(payment_amount, delegate_bump, ctx.remaining_accounts)?;
+    let payment_policy = &mut payment.payment_policy;
+    let user_payment = &mut payment.user_payment;
+
+    // Calculate next payment due time based on payment frequency. With catch-up
+    // only the paid period is advanced so outstanding periods stay collectable,
+    // otherwise every other period that became due is recorded as arrears
+    let (new_next_due, periods_advanced) = match &payment_frequency {
+        Some(frequency) if payment_policy.catch_up => {
+            (next_period_due(current_next_due, frequency)?, 1)
+        }
+        Some(frequency) => {
+            calculate_next_payment_due(current_next_due, frequency, clock.unix_timestamp)?
+        }
+        None => (current_next_due, 1),
+    };
+    let paid_period = payment_policy.periods_due.checked_add(1).unwrap();
+    payment_policy.periods_due = payment_policy
+        .periods_due
+        .checked_add(periods_advanced)
+        .unwrap();
+    payment_policy.arrears_periods = payment_policy
+        .arrears_periods
+        .checked_add(periods_advanced.saturating_sub(1))
+        .unwrap();
 
     // Update next_payment_due in policy_type
     match &mut payment_policy.policy_type {
@@ -191,6 +234,25 @@ pub fn handler_execute_payment(ctx: Context<ExecutePayment>) -> Result<()> {
         } => {
             *next_payment_due = new_next_due;
         }
+        PolicyType::Installment {
+            next_installment_due,
+            installments_completed,
+            ..
+        } => {
+            *next_installment_due = new_next_due;
+            *installments_completed = installments_completed.checked_add(1).unwrap();
+        }
+        PolicyType::OneTime { .. } => {}
+        PolicyType::Milestone { .. } => {
+            payment_policy.milestone_cursor =
+                payment_policy.milestone_cursor.checked_add(1).unwrap();
+        }
+        PolicyType::Metered {
+            next_payment_due, ..
+        } => {
+            *next_payment_due = new_next_due;
+        }
+        PolicyType::Stream { .. } => {}
     }
 
     // Update payment policy
@@ -201,19 +263,83 @@ pub fn handler_execute_payment(ctx: Context<ExecutePayment>) -> Result<()> {
     payment_policy.payment_count = payment_policy.payment_count.checked_add(1).unwrap();
     payment_policy.updated_at = clock.unix_timestamp;
 
-    // Check if payment count has reached max renewals and set status to Paused
+    // A successful retry clears the dunning state
+    if payment_policy.status == PaymentStatus::PastDue {
+        payment_policy.status = PaymentStatus::Active;
+        payment_policy.failed_attempts = 0;
+        payment_policy.first_failed_at = 0;
+        emit!(PaymentPolicyStatusChanged {
+            payment_policy: payment_policy.key(),
+            old_status: PaymentStatus::PastDue,
+            new_status: PaymentStatus::Active,
+        });
+    }
+
+    // At the end of a term an auto-renewing subscription starts a new one,
+    // otherwise it completes
     match &payment_policy.policy_type {
-        PolicyType::Subscription { max_renewals, .. } => {
-            if let Some(max_renewal) = max_renewals {
-                if payment_policy.payment_count >= *max_renewal {
-                    payment_policy.status = PaymentStatus::Paused;
-                }
+        PolicyType::Subscription { .. } => {
+            if payment_policy.subscription_finished() {
+                end_payment_policy(
+                    payment_policy,
+                    user_payment,
+                    PaymentStatus::Completed,
+                    clock.unix_timestamp,
+                );
+            } else if payment_policy.subscription_term_ended() {
+                renew_subscription_term(payment_policy, clock.unix_timestamp);
             }
         }
+        // Complete the plan once the last installment is paid
+        PolicyType::Installment {
+            num_installments,
+            installments_completed,
+            ..
+        } => {
+            if installments_completed >= num_installments {
+                end_payment_policy(
+                    payment_policy,
+                    user_payment,
+                    PaymentStatus::Completed,
+                    clock.unix_timestamp,
+                );
+            }
+        }
+        // A one-time payment completes after its single pull
+        PolicyType::OneTime { .. } => {
+            end_payment_policy(
+                payment_policy,
+                user_payment,
+                PaymentStatus::Completed,
+                clock.unix_timestamp,
+            );
+        }
+        // Complete the plan once the last milestone is paid
+        PolicyType::Milestone { milestones, .. } => {
+            if payment_policy.milestone_cursor >= PolicyType::milestone_count(milestones) {
+                end_payment_policy(
+                    payment_policy,
+                    user_payment,
+                    PaymentStatus::Completed,
+                    clock.unix_timestamp,
+                );
+            }
+        }
+        PolicyType::Metered { .. } | PolicyType::Stream { .. } => {}
     }
 
-    // Update gateway
-    gateway.total_processed = gateway.total_processed.checked_add(payment_amount).unwrap();
+    // Complete the subscription right away if the next payment would fall
+    // after its end date or exceed its lifetime cap
+    if payment_policy.status.is_active()
+        && payment_policy.subscription_limit_reached(payment_amount, new_next_due)
+    {
+        end_payment_policy(
+            payment_policy,
+            user_payment,
+            PaymentStatus::Completed,
+            clock.unix_timestamp,
+        );
+    }
 
     // Update user payment account
     user_payment.updated_at = clock.unix_timestamp;
@@ -221,18 +347,23 @@ pub fn handler_execute_payment(ctx: Context<ExecutePayment>) -> Result<()> {
     // Emit payment record event
     emit!(PaymentRecord {
         payment_policy: payment_policy.key(),
-        gateway: gateway.key(),
+        gateway: payment.gateway.key(),
         amount: payment_amount,
         timestamp: clock.unix_timestamp,
         memo: payment_policy.memo,
         record_id: payment_policy.payment_count,
+        transfer_fee: collected.transfer_fee,
+        usage_amount,
+        period: paid_period,
     });
+    payment.emit_delegated_allowance(payment_amount, &collected);
 
     msg!(
-        "Payment executed: {} tokens transferred to recipient, {} gateway fee, {} protocol fee",
-        recipient_amount,
-        gateway_fee,
-        protocol_fee
+        "Payment executed: {} tokens transferred to recipient, {} gateway fee, {} protocol fee, {} withheld by mint",
+        collected.recipient_amount,
+        collected.gateway_fee,
+        collected.protocol_fee,
+        collected.transfer_fee
     );
 
     Ok(())
@@ -0,0 +1,51 @@
+use crate::{
+    constants::*, error::RecurringPaymentsError, instructions::end_payment_policy, state::*,
+};
+use anchor_lang::prelude::*;
+
+/// Permissionless crank that expires a one-time payment after its grace period,
+/// it needs no token accounts so a revoked approval cannot keep the policy alive
+#[derive(Accounts)]
+pub struct ExpirePaymentPolicy<'info> {
+    #[account(
+        mut,
+        seeds = [PAYMENT_POLICY_SEED, payment_policy.user_payment.as_ref(), payment_policy.policy_id.to_le_bytes().as_ref()],
+        bump = payment_policy.bump,
+        constraint = !payment_policy.status.is_terminal() @ RecurringPaymentsError::InvalidPolicyStatusTransition,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+
+    #[account(
+        mut,
+        seeds = [USER_PAYMENT_SEED, user_payment.owner.as_ref(), user_payment.token_mint.as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.key() == payment_policy.user_payment,
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+}
+
+pub fn handler_expire_payment_policy(ctx: Context<ExpirePaymentPolicy>) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let user_payment = &mut ctx.accounts.user_payment;
+    let clock = Clock::get()?;
+
+    require!(
+        payment_policy.grace_period_elapsed(clock.unix_timestamp),
+        RecurringPaymentsError::PolicyNotExpired
+    );
+
+    end_payment_policy(
+        payment_policy,
+        user_payment,
+        PaymentStatus::Expired,
+        clock.unix_timestamp,
+    );
+    user_payment.updated_at = clock.unix_timestamp;
+
+    msg!(
+        "Payment policy ID: {} expired without payment",
+        payment_policy.policy_id
+    );
+
+    Ok(())
+}
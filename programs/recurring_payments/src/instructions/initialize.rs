@@ -1,4 +1,4 @@
-use crate::{state::*, CONFIG_SEED};
+use crate::{state::*, CONFIG_SEED, DEFAULT_MAX_GATEWAY_FEE_BPS};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -27,11 +27,15 @@ pub fn handle_initialize(ctx: Context<Initialize>) -> Result<()> {
     config.max_policies_per_user = 10;
     config.emergency_pause = false;
     config.bump = ctx.bumps.config;
+    config.guardian = Pubkey::default();
+    config.pending_admin = Pubkey::default();
+    config.max_gateway_fee_bps = DEFAULT_MAX_GATEWAY_FEE_BPS;
 
     emit!(ProgramConfigCreated {
         admin: config.admin,
         fee_recipient: config.fee_recipient,
         protocol_fee_bps: config.protocol_fee_bps,
+        max_gateway_fee_bps: config.max_gateway_fee_bps,
         max_policies_per_user: config.max_policies_per_user,
     });
 
@@ -0,0 +1,72 @@
+use crate::{constants::*, instructions::token_account_has_delegate, state::*};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{approve, Approve, Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct MigrateDelegate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    #[account(
+        constraint = token_mint.key() == user_payment.token_mint,
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.key() == user_payment.token_account,
+        constraint = token_account_has_delegate(&user_token_account, &legacy_delegate.key()) @ crate::error::RecurringPaymentsError::NoDelegateSet,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [PAYMENTS_SEED],
+        bump
+    )]
+    /// CHECK: Former program-wide delegate shared by all users
+    pub legacy_delegate: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [PAYMENTS_SEED, user_payment.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-derived delegate authority for this user payment
+    pub payments_delegate: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn handler_migrate_delegate(ctx: Context<MigrateDelegate>) -> Result<()> {
+    let amount = ctx.accounts.user_token_account.delegated_amount;
+
+    // Approving the new delegate replaces the legacy approval on the token account
+    let cpi_accounts = Approve {
+        to: ctx.accounts.user_token_account.to_account_info(),
+        delegate: ctx.accounts.payments_delegate.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    approve(cpi_ctx, amount)?;
+
+    emit!(DelegateMigrated {
+        user_payment: ctx.accounts.user_payment.key(),
+        old_delegate: ctx.accounts.legacy_delegate.key(),
+        new_delegate: ctx.accounts.payments_delegate.key(),
+        amount,
+    });
+
+    msg!(
+        "Delegate migrated for user payment: {}, amount: {}",
+        ctx.accounts.user_payment.key(),
+        amount
+    );
+
+    Ok(())
+}
@@ -1,19 +1,65 @@
+pub mod accept_admin;
+pub mod accept_policy_fees;
+pub mod approve_milestone;
 pub mod change_gateway_signer;
 pub mod change_payment_policy_status;
+pub mod change_policy_gateway;
 pub mod create_payment_gateway;
 pub mod create_payment_policy;
 pub mod create_user_payment;
 pub mod delete_payment_gateway;
 pub mod delete_payment_policy;
 pub mod execute_payment;
+pub mod expire_payment_policy;
 pub mod initialize;
+pub mod migrate_delegate;
+pub mod policy_payment;
+pub mod propose_admin;
+pub mod rebind_policy;
+pub mod report_failed_payment;
+pub mod retire_payment_gateway;
+pub mod set_auto_renew;
+pub mod set_emergency_pause;
+pub mod set_gateway_active;
+pub mod set_gateway_dunning;
+pub mod set_guardian;
+pub mod set_milestone_approval_mode;
+pub mod set_policy_catch_up;
+pub mod stop_stream;
+pub mod top_up_policy_allowance;
+pub mod update_payment_gateway;
+pub mod update_program_config;
+pub mod withdraw_stream;
 
+pub use accept_admin::*;
+pub use accept_policy_fees::*;
+pub use approve_milestone::*;
 pub use change_gateway_signer::*;
 pub use change_payment_policy_status::*;
+pub use change_policy_gateway::*;
 pub use create_payment_gateway::*;
 pub use create_payment_policy::*;
 pub use create_user_payment::*;
 pub use delete_payment_gateway::*;
 pub use delete_payment_policy::*;
 pub use execute_payment::*;
+pub use expire_payment_policy::*;
 pub use initialize::*;
+pub use migrate_delegate::*;
+pub use policy_payment::*;
+pub use propose_admin::*;
+pub use rebind_policy::*;
+pub use report_failed_payment::*;
+pub use retire_payment_gateway::*;
+pub use set_auto_renew::*;
+pub use set_emergency_pause::*;
+pub use set_gateway_active::*;
+pub use set_gateway_dunning::*;
+pub use set_guardian::*;
+pub use set_milestone_approval_mode::*;
+pub use set_policy_catch_up::*;
+pub use stop_stream::*;
+pub use top_up_policy_allowance::*;
+pub use update_payment_gateway::*;
+pub use update_program_config::*;
+pub use withdraw_stream::*;
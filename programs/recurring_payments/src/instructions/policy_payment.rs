@@ -0,0 +1,198 @@
+use crate::{
+    constants::*,
+    instructions::token_account_has_delegate,
+    state::*,
+    utils::{calculate_fees, transfer_payment},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+/// Accounts shared by the instructions that pull a payment for a policy from
+/// the user's token account into the recipient and fee accounts
+#[derive(Accounts)]
+pub struct PolicyPayment<'info> {
+    #[account(
+        seeds = [PAYMENTS_SEED, user_payment.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-derived delegate authority for this user's token transfers
+    pub payments_delegate: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [PAYMENT_POLICY_SEED, payment_policy.user_payment.as_ref(), payment_policy.policy_id.to_le_bytes().as_ref()],
+        bump = payment_policy.bump,
+        constraint = payment_policy.status.is_active() @ crate::error::RecurringPaymentsError::PolicyPaused,
+    )]
+    pub payment_policy: Box<Account<'info, PaymentPolicy>>,
+
+    #[account(
+        mut,
+        seeds = [USER_PAYMENT_SEED, user_payment.owner.as_ref(), user_payment.token_mint.as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.is_active,
+        constraint = user_payment.key() == payment_policy.user_payment,
+    )]
+    pub user_payment: Box<Account<'info, UserPayment>>,
+
+    #[account(
+        mut,
+        seeds = [GATEWAY_SEED, gateway.authority.as_ref()],
+        bump = gateway.bump,
+        constraint = gateway.is_active @ crate::error::RecurringPaymentsError::GatewayInactive,
+        constraint = gateway.key() == payment_policy.gateway,
+    )]
+    pub gateway: Box<Account<'info, PaymentGateway>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.emergency_pause @ crate::error::RecurringPaymentsError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        constraint = token_mint.key() == user_payment.token_mint,
+        mint::token_program = token_program,
+    )]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.key() == user_payment.token_account,
+        constraint = user_token_account.mint == user_payment.token_mint,
+        constraint = token_account_has_delegate(&user_token_account, &payments_delegate.key()) @ crate::error::RecurringPaymentsError::NoDelegateSet,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == user_payment.token_mint,
+        constraint = recipient_token_account.owner == payment_policy.recipient,
+    )]
+    pub recipient_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = gateway_fee_account.mint == user_payment.token_mint,
+        constraint = gateway_fee_account.owner == gateway.fee_recipient,
+    )]
+    pub gateway_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = protocol_fee_account.mint == user_payment.token_mint,
+        constraint = protocol_fee_account.owner == config.fee_recipient,
+    )]
+    pub protocol_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// The shares of a payment moved by `PolicyPayment::collect`
+pub struct CollectedPayment {
+    pub recipient_amount: u64,
+    pub gateway_fee: u64,
+    pub protocol_fee: u64,
+    /// Total fee withheld by the mint
+    pub transfer_fee: u64,
+    /// Approval left on the user's token account after the payment
+    pub remaining_allowance: u64,
+}
+
+impl<'info> PolicyPayment<'info> {
+    /// Returns how much of `amount` can be collected right now, bounded by the
+    /// user's balance, the delegated amount and the policy budget
+    pub fn collectable_amount(&self, amount: u64) -> u64 {
+        let collectable = amount
+            .min(self.user_token_account.amount)
+            .min(self.user_token_account.delegated_amount);
+        if self.payment_policy.allowance_enabled {
+            collectable.min(self.payment_policy.allowance_remaining)
+        } else {
+            collectable
+        }
+    }
+
+    /// Checks the user's balance and approval, consumes the policy budget and
+    /// transfers `amount` split into the recipient and fee shares. Transfer
+    /// hook accounts are passed as `remaining_accounts`.
+    pub fn collect(
+        &mut self,
+        amount: u64,
+        delegate_bump: u8,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<CollectedPayment> {
+        // Check if user has sufficient balance
+        require!(
+            self.user_token_account.amount >= amount,
+            crate::error::RecurringPaymentsError::InsufficientBalance
+        );
+
+        // Check if the delegate is still approved for the full payment
+        let delegated_amount = self.user_token_account.delegated_amount;
+        require!(
+            delegated_amount >= amount,
+            crate::error::RecurringPaymentsError::InsufficientDelegatedAmount
+        );
+
+        // Check and consume the policy's own budget
+        self.payment_policy.spend_allowance(amount)?;
+
+        // Calculate fees, never charging more than the subscriber agreed to
+        let (gateway_fee_bps, protocol_fee_bps) = self
+            .payment_policy
+            .effective_fees(self.gateway.gateway_fee_bps, self.config.protocol_fee_bps);
+        let (recipient_amount, gateway_fee, protocol_fee) =
+            calculate_fees(amount, gateway_fee_bps, protocol_fee_bps)?;
+
+        let user_payment_key = self.user_payment.key();
+        let seeds = &[PAYMENTS_SEED, user_payment_key.as_ref(), &[delegate_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_fee = transfer_payment(
+            &self.token_program,
+            &self.user_token_account,
+            &self.token_mint,
+            &self.recipient_token_account,
+            &self.gateway_fee_account,
+            &self.protocol_fee_account,
+            &self.payments_delegate,
+            remaining_accounts,
+            signer_seeds,
+            (recipient_amount, gateway_fee, protocol_fee),
+        )?;
+
+        // Update gateway
+        self.gateway.total_processed = self.gateway.total_processed.checked_add(amount).unwrap();
+
+        Ok(CollectedPayment {
+            recipient_amount,
+            gateway_fee,
+            protocol_fee,
+            transfer_fee,
+            remaining_allowance: delegated_amount - amount,
+        })
+    }
+
+    /// Report how many more periods the remaining approval covers, bounded by the
+    /// policy budget when one is set. Periods are counted at the amount due per
+    /// period (the cap for metered usage), streams count withdrawals of `amount`.
+    pub fn emit_delegated_allowance(&self, amount: u64, collected: &CollectedPayment) {
+        let period_amount = self.payment_policy.amount_due().unwrap_or(amount);
+        let covered_allowance = if self.payment_policy.allowance_enabled {
+            collected
+                .remaining_allowance
+                .min(self.payment_policy.allowance_remaining)
+        } else {
+            collected.remaining_allowance
+        };
+        emit!(DelegatedAllowance {
+            payment_policy: self.payment_policy.key(),
+            user_payment: self.user_payment.key(),
+            gateway: self.gateway.key(),
+            remaining_allowance: collected.remaining_allowance,
+            periods_remaining: covered_allowance.checked_div(period_amount).unwrap_or(0),
+        });
+    }
+}
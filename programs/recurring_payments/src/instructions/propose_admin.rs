@@ -0,0 +1,37 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ RecurringPaymentsError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: The proposed admin, it has to sign `accept_admin` to take over
+    pub new_admin: UncheckedAccount<'info>,
+}
+
+pub fn handler_propose_admin(ctx: Context<ProposeAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    config.pending_admin = ctx.accounts.new_admin.key();
+
+    emit!(AdminTransferProposed {
+        admin: config.admin,
+        pending_admin: config.pending_admin,
+    });
+
+    msg!(
+        "Admin transfer proposed from {:?} to {:?}",
+        config.admin,
+        config.pending_admin
+    );
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+/// Permissionless crank that moves a policy off a retired gateway onto its successor.
+/// A successor that was retired itself is accepted so that the crank can follow
+/// the chain of successors one gateway per call.
+#[derive(Accounts)]
+pub struct RebindPolicy<'info> {
+    #[account(
+        mut,
+        seeds = [PAYMENT_POLICY_SEED, payment_policy.user_payment.as_ref(), payment_policy.policy_id.to_le_bytes().as_ref()],
+        bump = payment_policy.bump,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+
+    #[account(
+        seeds = [GATEWAY_SEED, retired_gateway.authority.as_ref()],
+        bump = retired_gateway.bump,
+        constraint = retired_gateway.key() == payment_policy.gateway,
+        constraint = retired_gateway.is_retired() @ RecurringPaymentsError::InvalidSuccessorGateway,
+    )]
+    pub retired_gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [GATEWAY_SEED, successor_gateway.authority.as_ref()],
+        bump = successor_gateway.bump,
+        constraint = successor_gateway.key() == retired_gateway.successor @ RecurringPaymentsError::InvalidSuccessorGateway,
+        constraint = successor_gateway.is_active || successor_gateway.is_retired() @ RecurringPaymentsError::GatewayInactive,
+    )]
+    pub successor_gateway: Account<'info, PaymentGateway>,
+}
+
+pub fn handler_rebind_policy(ctx: Context<RebindPolicy>) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let clock = Clock::get()?;
+
+    // The fee snapshot is kept, a pricier successor needs the subscriber's acceptance
+    let old_gateway = payment_policy.gateway;
+    payment_policy.gateway = ctx.accounts.successor_gateway.key();
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(PaymentPolicyGatewayChanged {
+        payment_policy: payment_policy.key(),
+        old_gateway,
+        new_gateway: payment_policy.gateway,
+    });
+
+    msg!(
+        "Payment policy ID: {} rebound from retired gateway {:?} to {:?}",
+        payment_policy.policy_id,
+        old_gateway,
+        payment_policy.gateway
+    );
+
+    Ok(())
+}
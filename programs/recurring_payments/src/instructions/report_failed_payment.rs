@@ -0,0 +1,131 @@
+use crate::{constants::*, instructions::token_account_has_delegate, state::*};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+#[derive(Accounts)]
+pub struct ReportFailedPayment<'info> {
+    /// The gateway signer that failed to collect the payment
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PAYMENT_POLICY_SEED, payment_policy.user_payment.as_ref(), payment_policy.policy_id.to_le_bytes().as_ref()],
+        bump = payment_policy.bump,
+        constraint = payment_policy.status.is_active() @ crate::error::RecurringPaymentsError::PolicyPaused,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+
+    #[account(
+        mut,
+        seeds = [USER_PAYMENT_SEED, user_payment.owner.as_ref(), user_payment.token_mint.as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.key() == payment_policy.user_payment,
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    #[account(
+        seeds = [GATEWAY_SEED, gateway.authority.as_ref()],
+        bump = gateway.bump,
+        constraint = gateway.is_active @ crate::error::RecurringPaymentsError::GatewayInactive,
+        constraint = gateway.key() == payment_policy.gateway,
+        constraint = gateway.signer == signer.key(),
+    )]
+    pub gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.emergency_pause @ crate::error::RecurringPaymentsError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [PAYMENTS_SEED, user_payment.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-derived delegate authority for this user's token transfers
+    pub payments_delegate: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = user_token_account.key() == user_payment.token_account,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+pub fn handler_report_failed_payment(ctx: Context<ReportFailedPayment>) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let user_payment = &mut ctx.accounts.user_payment;
+    let gateway = &ctx.accounts.gateway;
+    let user_token_account = &ctx.accounts.user_token_account;
+    let clock = Clock::get()?;
+
+    // Only a due payment can fail
+    require!(
+        clock.unix_timestamp >= payment_policy.next_payment_due(),
+        crate::error::RecurringPaymentsError::PaymentNotDue
+    );
+
+    // The failure must be visible on-chain: missing delegation, balance or budget
+    let amount_due = payment_policy.amount_due()?;
+    let collectable =
+        token_account_has_delegate(user_token_account, &ctx.accounts.payments_delegate.key())
+            && user_token_account.amount >= amount_due
+            && user_token_account.delegated_amount >= amount_due
+            && (!payment_policy.allowance_enabled
+                || payment_policy.allowance_remaining >= amount_due);
+    require!(
+        !collectable,
+        crate::error::RecurringPaymentsError::PaymentStillCollectable
+    );
+
+    // Start a new dunning cycle or count another failed retry
+    let old_status = payment_policy.status.clone();
+    if old_status == PaymentStatus::Active {
+        payment_policy.failed_attempts = 0;
+        payment_policy.first_failed_at = clock.unix_timestamp;
+    }
+    payment_policy.failed_attempts = payment_policy.failed_attempts.saturating_add(1);
+
+    // Suspend once the retries are used up or the grace window has passed
+    let (max_payment_retries, retry_window_seconds) = gateway.dunning_settings();
+    let retries_exhausted = payment_policy.failed_attempts > max_payment_retries;
+    let window_elapsed = clock.unix_timestamp
+        >= payment_policy
+            .first_failed_at
+            .saturating_add(retry_window_seconds);
+    let new_status = if retries_exhausted || window_elapsed {
+        user_payment.remove_active_policy();
+        user_payment.updated_at = clock.unix_timestamp;
+        PaymentStatus::Suspended
+    } else {
+        PaymentStatus::PastDue
+    };
+    payment_policy.status = new_status.clone();
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(PaymentFailed {
+        payment_policy: payment_policy.key(),
+        gateway: gateway.key(),
+        failed_attempts: payment_policy.failed_attempts,
+        first_failed_at: payment_policy.first_failed_at,
+        status: new_status.clone(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    if old_status != new_status {
+        emit!(PaymentPolicyStatusChanged {
+            payment_policy: payment_policy.key(),
+            old_status,
+            new_status: new_status.clone(),
+        });
+    }
+
+    msg!(
+        "Payment failed for policy ID: {}, attempt {}, status: {:?}",
+        payment_policy.policy_id,
+        payment_policy.failed_attempts,
+        new_status
+    );
+
+    Ok(())
+}
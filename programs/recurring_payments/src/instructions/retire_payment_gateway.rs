@@ -0,0 +1,51 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RetirePaymentGateway<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GATEWAY_SEED, gateway.authority.as_ref()],
+        bump = gateway.bump,
+        constraint = !gateway.is_retired() @ RecurringPaymentsError::GatewayRetired,
+    )]
+    pub gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [GATEWAY_SEED, successor_gateway.authority.as_ref()],
+        bump = successor_gateway.bump,
+        constraint = successor_gateway.key() != gateway.key() @ RecurringPaymentsError::InvalidSuccessorGateway,
+        constraint = successor_gateway.is_active @ RecurringPaymentsError::GatewayInactive,
+    )]
+    pub successor_gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ RecurringPaymentsError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+pub fn handler_retire_payment_gateway(ctx: Context<RetirePaymentGateway>) -> Result<()> {
+    let gateway = &mut ctx.accounts.gateway;
+
+    gateway.is_active = false;
+    gateway.suspended_by_admin = true;
+    gateway.successor = ctx.accounts.successor_gateway.key();
+
+    emit!(PaymentGatewayRetired {
+        gateway: gateway.key(),
+        successor: gateway.successor,
+    });
+
+    msg!(
+        "Payment gateway {:?} retired, successor: {:?}",
+        gateway.key(),
+        gateway.successor
+    );
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use crate::{constants::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct SetAutoRenew<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+}
+
+pub fn handler_set_auto_renew(
+    ctx: Context<SetAutoRenew>,
+    _policy_id: u32,
+    auto_renew: bool,
+) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let clock = Clock::get()?;
+
+    // Only subscriptions run in renewable terms
+    match &mut payment_policy.policy_type {
+        PolicyType::Subscription {
+            auto_renew: current,
+            ..
+        } => *current = auto_renew,
+        _ => return err!(crate::error::RecurringPaymentsError::InvalidPolicyType),
+    }
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(AutoRenewChanged {
+        payment_policy: payment_policy.key(),
+        auto_renew,
+    });
+
+    msg!(
+        "Auto-renew {} for policy ID: {}",
+        if auto_renew { "enabled" } else { "disabled" },
+        payment_policy.policy_id
+    );
+
+    Ok(())
+}
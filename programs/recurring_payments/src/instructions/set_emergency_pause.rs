@@ -0,0 +1,46 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetEmergencyPause<'info> {
+    /// Either the admin or, for pausing only, the guardian
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+pub fn handler_set_emergency_pause(ctx: Context<SetEmergencyPause>, paused: bool) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let authority = ctx.accounts.authority.key();
+    let clock = Clock::get()?;
+
+    // The guardian may only pull the brake, lifting the pause requires the admin
+    let is_admin = config.admin == authority;
+    require!(
+        is_admin || (paused && config.is_guardian(&authority)),
+        RecurringPaymentsError::Unauthorized
+    );
+
+    config.emergency_pause = paused;
+
+    if paused {
+        emit!(ProgramPaused {
+            authority,
+            timestamp: clock.unix_timestamp,
+        });
+    } else {
+        emit!(ProgramUnpaused {
+            authority,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    msg!("Emergency pause set to {} by {:?}", paused, authority);
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetGatewayActive<'info> {
+    /// Either the program admin or the gateway authority
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GATEWAY_SEED, gateway.authority.as_ref()],
+        bump = gateway.bump,
+    )]
+    pub gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+pub fn handler_set_gateway_active(ctx: Context<SetGatewayActive>, active: bool) -> Result<()> {
+    let gateway = &mut ctx.accounts.gateway;
+    let authority = ctx.accounts.authority.key();
+
+    let is_admin = ctx.accounts.config.admin == authority;
+    let is_gateway_authority = gateway.authority == authority;
+    require!(
+        is_admin || is_gateway_authority,
+        RecurringPaymentsError::Unauthorized
+    );
+
+    if active {
+        require!(
+            !gateway.is_retired(),
+            RecurringPaymentsError::GatewayRetired
+        );
+    }
+
+    // A gateway suspended by the admin cannot be reinstated by its operator
+    if active && !is_admin {
+        require!(
+            !gateway.suspended_by_admin,
+            RecurringPaymentsError::Unauthorized
+        );
+    }
+
+    gateway.is_active = active;
+    gateway.suspended_by_admin = !active && (is_admin || gateway.suspended_by_admin);
+
+    emit!(PaymentGatewayStatusChanged {
+        gateway: gateway.key(),
+        changed_by: authority,
+        is_active: gateway.is_active,
+    });
+
+    msg!(
+        "Payment gateway {:?} active: {} (changed by {:?})",
+        gateway.key(),
+        gateway.is_active,
+        authority
+    );
+
+    Ok(())
+}
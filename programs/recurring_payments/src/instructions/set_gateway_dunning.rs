@@ -0,0 +1,45 @@
+use crate::{constants::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetGatewayDunning<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GATEWAY_SEED, authority.key().as_ref()],
+        bump = gateway.bump,
+        constraint = gateway.authority == authority.key()
+    )]
+    pub gateway: Account<'info, PaymentGateway>,
+}
+
+pub fn handler_set_gateway_dunning(
+    ctx: Context<SetGatewayDunning>,
+    max_payment_retries: u8,
+    retry_window_seconds: i64,
+) -> Result<()> {
+    require!(
+        max_payment_retries > 0 && retry_window_seconds > 0,
+        crate::error::RecurringPaymentsError::InvalidDunningSettings
+    );
+
+    let gateway = &mut ctx.accounts.gateway;
+    gateway.max_payment_retries = max_payment_retries;
+    gateway.retry_window_seconds = retry_window_seconds;
+
+    emit!(GatewayDunningUpdated {
+        gateway: gateway.key(),
+        max_payment_retries,
+        retry_window_seconds,
+    });
+
+    msg!(
+        "Gateway dunning updated: {} retries within {} seconds for gateway: {:?}",
+        max_payment_retries,
+        retry_window_seconds,
+        gateway.key()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ RecurringPaymentsError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+pub fn handler_set_guardian(ctx: Context<SetGuardian>, guardian: Option<Pubkey>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    let old_guardian = config.guardian;
+    config.guardian = guardian.unwrap_or_default();
+
+    emit!(GuardianChanged {
+        old_guardian,
+        new_guardian: config.guardian,
+    });
+
+    msg!(
+        "Guardian changed from {:?} to {:?}",
+        old_guardian,
+        config.guardian
+    );
+
+    Ok(())
+}
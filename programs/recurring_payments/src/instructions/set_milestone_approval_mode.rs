@@ -0,0 +1,63 @@
+use crate::{constants::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct SetMilestoneApprovalMode<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+        constraint = matches!(payment_policy.policy_type, PolicyType::Milestone { .. }) @ crate::error::RecurringPaymentsError::InvalidMilestone,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+}
+
+pub fn handler_set_milestone_approval_mode(
+    ctx: Context<SetMilestoneApprovalMode>,
+    _policy_id: u32,
+    approval_required: bool,
+) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let clock = Clock::get()?;
+
+    // Milestones already paid count as approved
+    payment_policy.milestone_approval_required = approval_required;
+    payment_policy.milestones_approved = payment_policy
+        .milestones_approved
+        .max(payment_policy.milestone_cursor);
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(MilestoneApprovalModeChanged {
+        payment_policy: payment_policy.key(),
+        approval_required,
+    });
+
+    msg!(
+        "Milestone approval {} for policy ID: {}",
+        if approval_required {
+            "required"
+        } else {
+            "not required"
+        },
+        payment_policy.policy_id
+    );
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::{constants::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct SetPolicyCatchUp<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+}
+
+pub fn handler_set_policy_catch_up(
+    ctx: Context<SetPolicyCatchUp>,
+    _policy_id: u32,
+    catch_up: bool,
+) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let clock = Clock::get()?;
+
+    payment_policy.catch_up = catch_up;
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(PolicyCatchUpChanged {
+        payment_policy: payment_policy.key(),
+        catch_up,
+    });
+
+    msg!(
+        "Catch-up {} for policy ID: {}",
+        if catch_up { "enabled" } else { "disabled" },
+        payment_policy.policy_id
+    );
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use crate::{constants::*, instructions::end_payment_policy, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct StopStream<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+        constraint = !payment_policy.status.is_terminal() @ crate::error::RecurringPaymentsError::InvalidPolicyStatusTransition,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+}
+
+pub fn handler_stop_stream(ctx: Context<StopStream>, _policy_id: u32) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let user_payment = &mut ctx.accounts.user_payment;
+    let clock = Clock::get()?;
+
+    // Stop accruing, whatever accrued so far stays withdrawable
+    match &mut payment_policy.policy_type {
+        PolicyType::Stream { stopped_at, .. } => {
+            require!(
+                stopped_at.is_none(),
+                crate::error::RecurringPaymentsError::InvalidStream
+            );
+            *stopped_at = Some(clock.unix_timestamp);
+        }
+        _ => return err!(crate::error::RecurringPaymentsError::InvalidPolicyType),
+    }
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(StreamStopped {
+        payment_policy: payment_policy.key(),
+        stopped_at: clock.unix_timestamp,
+    });
+
+    // Nothing left to pay out, the stream is done
+    let accrued = payment_policy.stream_accrued(clock.unix_timestamp)?;
+    if accrued == 0 {
+        end_payment_policy(
+            payment_policy,
+            user_payment,
+            PaymentStatus::Completed,
+            clock.unix_timestamp,
+        );
+    }
+
+    msg!(
+        "Stream stopped for policy ID: {}, {} tokens left to withdraw",
+        payment_policy.policy_id,
+        accrued
+    );
+
+    Ok(())
+}
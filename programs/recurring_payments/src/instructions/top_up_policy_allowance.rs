@@ -0,0 +1,60 @@
+use crate::{constants::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(policy_id: u32)]
+pub struct TopUpPolicyAllowance<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [USER_PAYMENT_SEED, owner.key().as_ref(), token_mint.key().as_ref()],
+        bump = user_payment.bump,
+        constraint = user_payment.owner == owner.key(),
+    )]
+    pub user_payment: Account<'info, UserPayment>,
+
+    /// CHECK: This is the token mint for the payment
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            PAYMENT_POLICY_SEED,
+            user_payment.key().as_ref(),
+            policy_id.to_le_bytes().as_ref()
+        ],
+        bump = payment_policy.bump,
+    )]
+    pub payment_policy: Account<'info, PaymentPolicy>,
+}
+
+pub fn handler_top_up_policy_allowance(
+    ctx: Context<TopUpPolicyAllowance>,
+    _policy_id: u32,
+    amount: u64,
+) -> Result<()> {
+    let payment_policy = &mut ctx.accounts.payment_policy;
+    let clock = Clock::get()?;
+
+    // The first top-up turns on the per-policy budget
+    payment_policy.allowance_enabled = true;
+    payment_policy.allowance_remaining = payment_policy
+        .allowance_remaining
+        .checked_add(amount)
+        .ok_or(crate::error::RecurringPaymentsError::MathOverflow)?;
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    emit!(PolicyAllowanceToppedUp {
+        payment_policy: payment_policy.key(),
+        amount,
+        allowance_remaining: payment_policy.allowance_remaining,
+    });
+
+    msg!(
+        "Allowance topped up for policy ID: {}, remaining: {}",
+        payment_policy.policy_id,
+        payment_policy.allowance_remaining
+    );
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdatePaymentGateway<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GATEWAY_SEED, authority.key().as_ref()],
+        bump = gateway.bump,
+        constraint = gateway.authority == authority.key() @ RecurringPaymentsError::Unauthorized
+    )]
+    pub gateway: Account<'info, PaymentGateway>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: This is the fee recipient account that will receive gateway fees
+    pub fee_recipient: UncheckedAccount<'info>,
+}
+
+pub fn handler_update_payment_gateway(
+    ctx: Context<UpdatePaymentGateway>,
+    gateway_fee_bps: u16,
+    name: [u8; 32],
+    url: [u8; 64],
+) -> Result<()> {
+    ctx.accounts.config.validate_gateway_fee(gateway_fee_bps)?;
+
+    let gateway = &mut ctx.accounts.gateway;
+
+    let old_fee_recipient = gateway.fee_recipient;
+    let old_gateway_fee_bps = gateway.gateway_fee_bps;
+    let old_name = gateway.name;
+    let old_url = gateway.url;
+
+    gateway.fee_recipient = ctx.accounts.fee_recipient.key();
+    gateway.gateway_fee_bps = gateway_fee_bps;
+    gateway.name = name;
+    gateway.url = url;
+
+    emit!(PaymentGatewayUpdated {
+        gateway: gateway.key(),
+        old_fee_recipient,
+        new_fee_recipient: gateway.fee_recipient,
+        old_gateway_fee_bps,
+        new_gateway_fee_bps: gateway.gateway_fee_bps,
+        old_name,
+        new_name: gateway.name,
+        old_url,
+        new_url: gateway.url,
+    });
+
+    msg!(
+        "Payment gateway updated: {:?}, fee: {} -> {} bps, name: {:?}, url: {:?}",
+        gateway.key(),
+        old_gateway_fee_bps,
+        gateway.gateway_fee_bps,
+        String::from_utf8_lossy(&name),
+        String::from_utf8_lossy(&url)
+    );
+
+    Ok(())
+}
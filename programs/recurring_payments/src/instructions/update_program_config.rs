@@ -0,0 +1,69 @@
+use crate::{constants::*, error::RecurringPaymentsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateProgramConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ RecurringPaymentsError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: This is the fee recipient account that will receive protocol fees
+    pub fee_recipient: UncheckedAccount<'info>,
+}
+
+pub fn handler_update_program_config(
+    ctx: Context<UpdateProgramConfig>,
+    protocol_fee_bps: u16,
+    max_gateway_fee_bps: u16,
+    max_policies_per_user: u32,
+) -> Result<()> {
+    require!(
+        protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+        RecurringPaymentsError::InvalidFeeBps
+    );
+    // 0 is reserved for configs created before the cap existed, which use the default
+    require!(
+        max_gateway_fee_bps > 0,
+        RecurringPaymentsError::InvalidFeeBps
+    );
+    // Fees of any gateway within the cap must not exceed the payment amount
+    require!(
+        (protocol_fee_bps as u64) + (max_gateway_fee_bps as u64) <= BPS_DENOMINATOR,
+        RecurringPaymentsError::InvalidFeeBps
+    );
+    require!(
+        max_policies_per_user > 0,
+        RecurringPaymentsError::InvalidMaxPolicies
+    );
+
+    let config = &mut ctx.accounts.config;
+
+    config.fee_recipient = ctx.accounts.fee_recipient.key();
+    config.protocol_fee_bps = protocol_fee_bps;
+    config.max_gateway_fee_bps = max_gateway_fee_bps;
+    config.max_policies_per_user = max_policies_per_user;
+
+    emit!(ProgramConfigUpdated {
+        admin: config.admin,
+        fee_recipient: config.fee_recipient,
+        protocol_fee_bps: config.protocol_fee_bps,
+        max_gateway_fee_bps: config.max_gateway_fee_bps,
+        max_policies_per_user: config.max_policies_per_user,
+    });
+
+    msg!(
+        "Program config updated: fee recipient: {:?}, protocol fee: {} bps, max gateway fee: {} bps, max policies per user: {}",
+        config.fee_recipient,
+        config.protocol_fee_bps,
+        config.max_gateway_fee_bps,
+        config.max_policies_per_user
+    );
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::{
+    instructions::{end_payment_policy, policy_payment::*},
+    state::*,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct WithdrawStream<'info> {
+    /// CHECK: The stream recipient or the gateway signer on their behalf
+    pub authority: Signer<'info>,
+
+    #[account(
+        constraint = payment.gateway.signer == authority.key() || payment.payment_policy.recipient == authority.key(),
+    )]
+    pub payment: PolicyPayment<'info>,
+}
+
+/// Pays out `amount` of what a stream accrued since the last withdrawal, or as
+/// much of it as the user's balance, approval and budget cover when `amount` is
+/// `None`. Only whole seconds of accrual are paid out. Transfer hook accounts
+/// are passed as remaining accounts, as for `execute_payment`.
+pub fn handler_withdraw_stream<'info>(
+    ctx: Context<'_, '_, 'info, 'info, WithdrawStream<'info>>,
+    amount: Option<u64>,
+) -> Result<()> {
+    let delegate_bump = ctx.bumps.payment.payments_delegate;
+    let payment = &mut ctx.accounts.payment;
+    let clock = Clock::get()?;
+
+    // Get the accrued amount from the stream
+    let accrued = payment
+        .payment_policy
+        .stream_accrued(clock.unix_timestamp)?;
+    let requested = match amount {
+        Some(amount) => {
+            require!(
+                amount <= accrued,
+                crate::error::RecurringPaymentsError::InvalidAmount
+            );
+            amount
+        }
+        None => payment.collectable_amount(accrued),
+    };
+    let (payment_amount, withdrawn_until) = payment.payment_policy.stream_withdrawal(requested)?;
+    require!(
+        payment_amount > 0,
+        crate::error::RecurringPaymentsError::NothingToWithdraw
+    );
+
+    let collected = payment.collect(payment_amount, delegate_bump, ctx.remaining_accounts)?;
+    let payment_policy = &mut payment.payment_policy;
+    let user_payment = &mut payment.user_payment;
+
+    // Move the withdrawal checkpoint and complete stopped streams that are fully paid out
+    let mut fully_withdrawn = false;
+    if let PolicyType::Stream {
+        last_withdrawn_at,
+        stopped_at,
+        ..
+    } = &mut payment_policy.policy_type
+    {
+        *last_withdrawn_at = withdrawn_until;
+        fully_withdrawn = stopped_at.is_some_and(|stopped| withdrawn_until >= stopped);
+    }
+
+    // Update payment policy
+    payment_policy.total_paid = payment_policy
+        .total_paid
+        .checked_add(payment_amount)
+        .unwrap();
+    payment_policy.payment_count = payment_policy.payment_count.checked_add(1).unwrap();
+    payment_policy.periods_due = payment_policy.periods_due.checked_add(1).unwrap();
+    payment_policy.updated_at = clock.unix_timestamp;
+
+    if fully_withdrawn {
+        end_payment_policy(
+            payment_policy,
+            user_payment,
+            PaymentStatus::Completed,
+            clock.unix_timestamp,
+        );
+    }
+
+    // Update user payment account
+    user_payment.updated_at = clock.unix_timestamp;
+
+    // Emit payment record event
+    emit!(PaymentRecord {
+        payment_policy: payment_policy.key(),
+        gateway: payment.gateway.key(),
+        amount: payment_amount,
+        timestamp: clock.unix_timestamp,
+        memo: payment_policy.memo,
+        record_id: payment_policy.payment_count,
+        transfer_fee: collected.transfer_fee,
+        usage_amount: None,
+        period: payment_policy.periods_due,
+    });
+    payment.emit_delegated_allowance(payment_amount, &collected);
+
+    msg!(
+        "Stream withdrawn: {} tokens transferred to recipient, {} gateway fee, {} protocol fee, {} withheld by mint",
+        collected.recipient_amount,
+        collected.gateway_fee,
+        collected.protocol_fee,
+        collected.transfer_fee
+    );
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 #![allow(unexpected_cfgs)]
 #![allow(clippy::result_large_err)]
+#![allow(deprecated)]
 
 pub mod constants;
 pub mod error;
@@ -23,6 +24,36 @@ pub mod recurring_payments {
         instructions::initialize::handle_initialize(ctx)
     }
 
+    pub fn update_program_config(
+        ctx: Context<UpdateProgramConfig>,
+        protocol_fee_bps: u16,
+        max_gateway_fee_bps: u16,
+        max_policies_per_user: u32,
+    ) -> Result<()> {
+        instructions::update_program_config::handler_update_program_config(
+            ctx,
+            protocol_fee_bps,
+            max_gateway_fee_bps,
+            max_policies_per_user,
+        )
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>) -> Result<()> {
+        instructions::propose_admin::handler_propose_admin(ctx)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin::handler_accept_admin(ctx)
+    }
+
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Option<Pubkey>) -> Result<()> {
+        instructions::set_guardian::handler_set_guardian(ctx, guardian)
+    }
+
+    pub fn set_emergency_pause(ctx: Context<SetEmergencyPause>, paused: bool) -> Result<()> {
+        instructions::set_emergency_pause::handler_set_emergency_pause(ctx, paused)
+    }
+
     pub fn create_user_payment(ctx: Context<CreateUserPayment>) -> Result<()> {
         instructions::create_user_payment::handler_create_user_payment(ctx)
     }
@@ -41,6 +72,36 @@ pub mod recurring_payments {
         )
     }
 
+    pub fn update_payment_gateway(
+        ctx: Context<UpdatePaymentGateway>,
+        gateway_fee_bps: u16,
+        name: [u8; 32],
+        url: [u8; 64],
+    ) -> Result<()> {
+        instructions::update_payment_gateway::handler_update_payment_gateway(
+            ctx,
+            gateway_fee_bps,
+            name,
+            url,
+        )
+    }
+
+    pub fn set_gateway_active(ctx: Context<SetGatewayActive>, active: bool) -> Result<()> {
+        instructions::set_gateway_active::handler_set_gateway_active(ctx, active)
+    }
+
+    pub fn retire_payment_gateway(ctx: Context<RetirePaymentGateway>) -> Result<()> {
+        instructions::retire_payment_gateway::handler_retire_payment_gateway(ctx)
+    }
+
+    pub fn rebind_policy(ctx: Context<RebindPolicy>) -> Result<()> {
+        instructions::rebind_policy::handler_rebind_policy(ctx)
+    }
+
+    pub fn expire_payment_policy(ctx: Context<ExpirePaymentPolicy>) -> Result<()> {
+        instructions::expire_payment_policy::handler_expire_payment_policy(ctx)
+    }
+
     pub fn create_payment_policy(
         ctx: Context<CreatePaymentPolicy>,
         policy_id: u32,
@@ -55,8 +116,26 @@ pub mod recurring_payments {
         )
     }
 
-    pub fn execute_payment(ctx: Context<ExecutePayment>) -> Result<()> {
-        instructions::execute_payment::handler_execute_payment(ctx)
+    pub fn execute_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecutePayment<'info>>,
+        usage_amount: Option<u64>,
+    ) -> Result<()> {
+        instructions::execute_payment::handler_execute_payment(ctx, usage_amount)
+    }
+
+    pub fn report_failed_payment(ctx: Context<ReportFailedPayment>) -> Result<()> {
+        instructions::report_failed_payment::handler_report_failed_payment(ctx)
+    }
+
+    pub fn withdraw_stream<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawStream<'info>>,
+        amount: Option<u64>,
+    ) -> Result<()> {
+        instructions::withdraw_stream::handler_withdraw_stream(ctx, amount)
+    }
+
+    pub fn stop_stream(ctx: Context<StopStream>, policy_id: u32) -> Result<()> {
+        instructions::stop_stream::handler_stop_stream(ctx, policy_id)
     }
 
     pub fn change_payment_policy_status(
@@ -69,6 +148,64 @@ pub mod recurring_payments {
         )
     }
 
+    pub fn change_policy_gateway(ctx: Context<ChangePolicyGateway>, policy_id: u32) -> Result<()> {
+        instructions::change_policy_gateway::handler_change_policy_gateway(ctx, policy_id)
+    }
+
+    pub fn accept_policy_fees(ctx: Context<AcceptPolicyFees>, policy_id: u32) -> Result<()> {
+        instructions::accept_policy_fees::handler_accept_policy_fees(ctx, policy_id)
+    }
+
+    pub fn top_up_policy_allowance(
+        ctx: Context<TopUpPolicyAllowance>,
+        policy_id: u32,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::top_up_policy_allowance::handler_top_up_policy_allowance(
+            ctx, policy_id, amount,
+        )
+    }
+
+    pub fn set_milestone_approval_mode(
+        ctx: Context<SetMilestoneApprovalMode>,
+        policy_id: u32,
+        approval_required: bool,
+    ) -> Result<()> {
+        instructions::set_milestone_approval_mode::handler_set_milestone_approval_mode(
+            ctx,
+            policy_id,
+            approval_required,
+        )
+    }
+
+    pub fn set_policy_catch_up(
+        ctx: Context<SetPolicyCatchUp>,
+        policy_id: u32,
+        catch_up: bool,
+    ) -> Result<()> {
+        instructions::set_policy_catch_up::handler_set_policy_catch_up(ctx, policy_id, catch_up)
+    }
+
+    pub fn set_auto_renew(
+        ctx: Context<SetAutoRenew>,
+        policy_id: u32,
+        auto_renew: bool,
+    ) -> Result<()> {
+        instructions::set_auto_renew::handler_set_auto_renew(ctx, policy_id, auto_renew)
+    }
+
+    pub fn approve_milestone(
+        ctx: Context<ApproveMilestone>,
+        policy_id: u32,
+        milestone_index: u8,
+    ) -> Result<()> {
+        instructions::approve_milestone::handler_approve_milestone(ctx, policy_id, milestone_index)
+    }
+
+    pub fn migrate_delegate(ctx: Context<MigrateDelegate>) -> Result<()> {
+        instructions::migrate_delegate::handler_migrate_delegate(ctx)
+    }
+
     pub fn delete_payment_policy(ctx: Context<DeletePaymentPolicy>, policy_id: u32) -> Result<()> {
         instructions::delete_payment_policy::handler_delete_payment_policy(ctx, policy_id)
     }
@@ -80,4 +217,16 @@ pub mod recurring_payments {
     pub fn change_gateway_signer(ctx: Context<ChangeGatewaySigner>) -> Result<()> {
         instructions::change_gateway_signer::handler_change_gateway_signer(ctx)
     }
+
+    pub fn set_gateway_dunning(
+        ctx: Context<SetGatewayDunning>,
+        max_payment_retries: u8,
+        retry_window_seconds: i64,
+    ) -> Result<()> {
+        instructions::set_gateway_dunning::handler_set_gateway_dunning(
+            ctx,
+            max_payment_retries,
+            retry_window_seconds,
+        )
+    }
 }
@@ -1,8 +1,14 @@
+use crate::constants::{
+    DEFAULT_MAX_GATEWAY_FEE_BPS, DEFAULT_MAX_PAYMENT_RETRIES, DEFAULT_RETRY_WINDOW_SECONDS,
+};
 use anchor_lang::prelude::*;
 
-/// The PolicyType enum implements the payment schemes. The initial policy
-/// will be a subscription payment that enables the regular payment according to
-/// a schedule.
+/// The PolicyType enum implements the payment schemes. A subscription enables
+/// the regular payment according to a schedule, an installment plan splits a
+/// fixed total into a number of scheduled payments, a one-time payment
+/// collects a single invoice within a grace period, a milestone plan pays a
+/// fixed schedule of amounts, a metered policy charges the usage reported by
+/// the gateway each period and a stream accrues value every second.
 ///
 /// IMPORTANT: All variants MUST be exactly 128 bytes to ensure consistent account sizing
 /// and enable future enum variant additions without breaking existing accounts.
@@ -14,30 +20,44 @@ pub enum PolicyType {
         max_renewals: Option<u32>,           // 5 bytes (1 + 4)
         payment_frequency: PaymentFrequency, // 9 bytes (1 + 8)
         next_payment_due: i64,               // 8 bytes
-        padding: [u8; 97],                   // 97 bytes padding
+        max_total_amount: u64,               // 8 bytes - 0 means no lifetime cap
+        end_timestamp: i64,                  // 8 bytes - 0 means no end date
+        padding: [u8; 81],                   // 81 bytes padding
+    },
+    Installment {
+        total_amount: u64,           // 8 bytes - Maximum amount that can be withdrawn
+        num_installments: u32,       // 4 bytes - Number of installments
+        installment_amount: u64,     // 8 bytes - total_amount / num_installments
+        period: PaymentFrequency,    // 9 bytes - Frequency of installments
+        start_date: i64,             // 8 bytes - When installments begin
+        next_installment_due: i64,   // 8 bytes - Next payment timestamp
+        installments_completed: u32, // 4 bytes - Track progress
+        padding: [u8; 79],           // 79 bytes padding
+    },
+    OneTime {
+        amount: u64,               // 8 bytes
+        due_date: i64,             // 8 bytes
+        grace_period_seconds: u64, // 8 bytes
+        padding: [u8; 104],        // 104 bytes padding
+    },
+    Milestone {
+        milestones: [u64; 8], // 64 bytes (8 payments, unused trailing slots are 0)
+        intervals: [u64; 8],  // 64 bytes (seconds after the previous milestone)
+                              // no padding (exactly 128 bytes used)
+    },
+    Metered {
+        max_amount_per_period: u64,          // 8 bytes
+        payment_frequency: PaymentFrequency, // 9 bytes (1 + 8)
+        next_payment_due: i64,               // 8 bytes
+        padding: [u8; 103],                  // 103 bytes padding
+    },
+    Stream {
+        rate_per_second: u64,    // 8 bytes
+        start_time: i64,         // 8 bytes
+        last_withdrawn_at: i64,  // 8 bytes
+        stopped_at: Option<i64>, // 9 bytes (1 + 8)
+        padding: [u8; 95],       // 95 bytes padding
     },
-    // Future variants can be added like this:
-    // Installment {
-    //     total_amount: u64,              // 8 bytes - Maximum amount that can be withdrawn (X$)
-    //     num_installments: u32,          // 4 bytes - Number of installments (Y)
-    //     installment_amount: u64,        // 8 bytes - Amount per installment (total_amount / num_installments)
-    //     period: PaymentFrequency,       // 9 bytes - Frequency of installments (e.g., Monthly)
-    //     start_date: i64,                // 8 bytes - When installments begin
-    //     next_installment_due: i64,      // 8 bytes - Next payment timestamp
-    //     installments_completed: u32,    // 4 bytes - Track progress
-    //     padding: [u8; 87],              // 87 bytes padding (total: 8+4+8+9+8+8+4+87=128)
-    // },
-    // OneTime {
-    //     amount: u64,                // 8 bytes
-    //     due_date: i64,              // 8 bytes
-    //     grace_period_seconds: u64,  // 8 bytes
-    //     padding: [u8; 104],        // 104 bytes padding
-    // },
-    // Milestone {
-    //     milestones: [u64; 8],       // 64 bytes (8 payments)
-    //     intervals: [u64; 8],        // 64 bytes (time intervals)
-    //     padding: [u8; 0],          // 0 bytes padding (exactly 128 bytes used)
-    // },
 }
 
 impl PolicyType {
@@ -47,6 +67,27 @@ impl PolicyType {
     /// Total size including enum discriminator
     pub const TOTAL_SIZE: usize = 1 + Self::VARIANT_SIZE; // 129 bytes
 
+    /// Amount of the next installment, the final one absorbs the rounding
+    /// remainder so that exactly `total_amount` is paid
+    pub fn installment_due_amount(
+        total_amount: u64,
+        num_installments: u32,
+        installment_amount: u64,
+        installments_completed: u32,
+    ) -> u64 {
+        if installments_completed.saturating_add(1) >= num_installments {
+            let paid = installment_amount.saturating_mul(installments_completed as u64);
+            total_amount.saturating_sub(paid)
+        } else {
+            installment_amount
+        }
+    }
+
+    /// Number of milestones in the plan, they end at the first zero amount
+    pub fn milestone_count(milestones: &[u64; 8]) -> u8 {
+        milestones.iter().take_while(|amount| **amount > 0).count() as u8
+    }
+
     /// Validates the policy type and its parameters
     pub fn validate(&self) -> Result<()> {
         match self {
@@ -54,6 +95,9 @@ impl PolicyType {
                 amount,
                 payment_frequency,
                 max_renewals,
+                max_total_amount,
+                end_timestamp,
+                next_payment_due,
                 ..
             } => {
                 // Validate amount is greater than zero
@@ -72,6 +116,101 @@ impl PolicyType {
                         crate::error::RecurringPaymentsError::InvalidInterval
                     );
                 }
+
+                // Validate the lifetime cap covers at least one payment
+                if *max_total_amount > 0 {
+                    require!(
+                        *max_total_amount >= *amount,
+                        crate::error::RecurringPaymentsError::InvalidAmount
+                    );
+                }
+
+                // Validate the end date comes after the first payment
+                if *end_timestamp != 0 {
+                    require!(
+                        *end_timestamp > *next_payment_due,
+                        crate::error::RecurringPaymentsError::InvalidEndTimestamp
+                    );
+                }
+            }
+            PolicyType::Installment {
+                total_amount,
+                num_installments,
+                installment_amount,
+                period,
+                start_date,
+                next_installment_due,
+                installments_completed,
+                ..
+            } => {
+                // Validate every installment pays something
+                require!(
+                    *num_installments > 0 && *total_amount >= *num_installments as u64,
+                    crate::error::RecurringPaymentsError::InvalidAmount
+                );
+
+                // Validate the installment amount matches the plan
+                require!(
+                    *installment_amount == *total_amount / *num_installments as u64,
+                    crate::error::RecurringPaymentsError::InvalidInstallmentPlan
+                );
+
+                // Validate installment period
+                period.validate()?;
+
+                // Validate the plan starts from the first installment
+                require!(
+                    *installments_completed == 0 && *next_installment_due == *start_date,
+                    crate::error::RecurringPaymentsError::InvalidInstallmentPlan
+                );
+            }
+            PolicyType::OneTime { amount, .. } => {
+                // Validate amount is greater than zero
+                require!(
+                    *amount > 0,
+                    crate::error::RecurringPaymentsError::InvalidAmount
+                );
+            }
+            PolicyType::Milestone { milestones, .. } => {
+                // Validate at least one milestone and no gaps between them
+                let count = Self::milestone_count(milestones) as usize;
+                require!(
+                    count > 0 && milestones[count..].iter().all(|amount| *amount == 0),
+                    crate::error::RecurringPaymentsError::InvalidMilestone
+                );
+            }
+            PolicyType::Metered {
+                max_amount_per_period,
+                payment_frequency,
+                ..
+            } => {
+                // Validate the cap allows charging something
+                require!(
+                    *max_amount_per_period > 0,
+                    crate::error::RecurringPaymentsError::InvalidAmount
+                );
+
+                // Validate payment frequency
+                payment_frequency.validate()?;
+            }
+            PolicyType::Stream {
+                rate_per_second,
+                start_time,
+                last_withdrawn_at,
+                stopped_at,
+                ..
+            } => {
+                // Validate the stream accrues something
+                require!(
+                    *rate_per_second > 0,
+                    crate::error::RecurringPaymentsError::InvalidAmount
+                );
+
+                // Validate the stream starts running and untouched
+                require!(
+                    *last_withdrawn_at == *start_time && stopped_at.is_none(),
+                    crate::error::RecurringPaymentsError::InvalidStream
+                );
             }
         }
         Ok(())
@@ -83,6 +222,45 @@ impl PolicyType {
 pub enum PaymentStatus {
     Active,
     Paused,
+    Completed,
+    Expired,
+    PastDue,
+    Suspended,
+    Cancelled,
+}
+
+impl PaymentStatus {
+    /// Whether the policy has finished and can no longer be resumed
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            PaymentStatus::Completed | PaymentStatus::Expired | PaymentStatus::Cancelled
+        )
+    }
+
+    /// Whether the policy owner may move a policy from this status to `new_status`.
+    /// `PastDue`, `Suspended`, `Completed` and `Expired` are only reached by the program.
+    pub fn can_transition_to(&self, new_status: &PaymentStatus) -> bool {
+        matches!(
+            (self, new_status),
+            (PaymentStatus::Active, PaymentStatus::Paused)
+                | (PaymentStatus::Paused, PaymentStatus::Active)
+                | (PaymentStatus::Suspended, PaymentStatus::Active)
+                | (
+                    PaymentStatus::Active
+                        | PaymentStatus::Paused
+                        | PaymentStatus::PastDue
+                        | PaymentStatus::Suspended,
+                    PaymentStatus::Cancelled
+                )
+        )
+    }
+
+    /// Whether a policy in this status counts towards `UserPayment.active_policies_count`
+    /// and can be charged
+    pub fn is_active(&self) -> bool {
+        matches!(self, PaymentStatus::Active | PaymentStatus::PastDue)
+    }
 }
 
 /// Simplify the payment frequency while also allowing a custom period as well,
@@ -101,14 +279,11 @@ pub enum PaymentFrequency {
 impl PaymentFrequency {
     /// Validates the payment frequency
     pub fn validate(&self) -> Result<()> {
-        match self {
-            PaymentFrequency::Custom(interval) => {
-                require!(
-                    *interval > 0,
-                    crate::error::RecurringPaymentsError::InvalidFrequency
-                );
-            }
-            _ => {}
+        if let PaymentFrequency::Custom(interval) = self {
+            require!(
+                *interval > 0,
+                crate::error::RecurringPaymentsError::InvalidFrequency
+            );
         }
         Ok(())
     }
@@ -128,7 +303,9 @@ pub struct UserPayment {
     pub updated_at: i64,
     pub is_active: bool,
     pub bump: u8,
-    pub padding: [u8; 256],
+    /// Highest policy id ever created, used to derive the next free policy id
+    pub last_policy_id: u32,
+    pub padding: [u8; 252],
 }
 
 impl UserPayment {
@@ -141,7 +318,23 @@ impl UserPayment {
         8 + // updated_at: i64
         1 + // is_active: bool
         1 + // bump: u8
-        256; // padding: [u8; 256]
+        4 + // last_policy_id: u32
+        252; // padding: [u8; 252]
+
+    /// Account for one more active policy, failing once the configured cap is reached
+    pub fn add_active_policy(&mut self, max_policies_per_user: u32) -> Result<()> {
+        require!(
+            self.active_policies_count < max_policies_per_user,
+            crate::error::RecurringPaymentsError::MaxPoliciesReached
+        );
+        self.active_policies_count += 1;
+        Ok(())
+    }
+
+    /// Account for one less active policy
+    pub fn remove_active_policy(&mut self) {
+        self.active_policies_count = self.active_policies_count.saturating_sub(1);
+    }
 }
 
 /// A gateway operator runs the service that triggers payment.
@@ -161,7 +354,18 @@ pub struct PaymentGateway {
     pub url: [u8; 64],
     /// This signer key is to execute payments
     pub signer: Pubkey,
-    pub padding: [u8; 128],
+    /// Set when the admin suspended the gateway, only the admin can reinstate it then
+    pub suspended_by_admin: bool,
+    /// Gateway that takes over the policies of this one once it is retired.
+    /// `Pubkey::default()` means the gateway is not retired.
+    pub successor: Pubkey,
+    /// Failed retries tolerated before a past due policy is suspended,
+    /// 0 means `DEFAULT_MAX_PAYMENT_RETRIES`
+    pub max_payment_retries: u8,
+    /// Seconds after the first failed payment before a past due policy is
+    /// suspended, 0 means `DEFAULT_RETRY_WINDOW_SECONDS`
+    pub retry_window_seconds: i64,
+    pub padding: [u8; 86],
 }
 
 impl PaymentGateway {
@@ -176,7 +380,30 @@ impl PaymentGateway {
         32 + // name: [u8; 32]
         64 + // url: [u8; 64]
         32 + // signer: Pubkey
-        128; // padding: [u8; 160]
+        1 + // suspended_by_admin: bool
+        32 + // successor: Pubkey
+        1 + // max_payment_retries: u8
+        8 + // retry_window_seconds: i64
+        86; // padding: [u8; 86]
+
+    /// Returns true if the gateway has been retired in favour of a successor
+    pub fn is_retired(&self) -> bool {
+        self.successor != Pubkey::default()
+    }
+
+    /// Returns the (max_payment_retries, retry_window_seconds) dunning settings,
+    /// falling back to the defaults for gateways that never configured them
+    pub fn dunning_settings(&self) -> (u8, i64) {
+        let max_payment_retries = match self.max_payment_retries {
+            0 => DEFAULT_MAX_PAYMENT_RETRIES,
+            retries => retries,
+        };
+        let retry_window_seconds = match self.retry_window_seconds {
+            0 => DEFAULT_RETRY_WINDOW_SECONDS,
+            window => window,
+        };
+        (max_payment_retries, retry_window_seconds)
+    }
 }
 
 /// This structure connects a UserPayment (user/mint) with a Policy, a Gateway.
@@ -196,7 +423,39 @@ pub struct PaymentPolicy {
     pub updated_at: i64,
     pub policy_id: u32,
     pub bump: u8,
-    pub padding: [u8; 256],
+    /// Gateway fee the subscriber agreed to, increases need explicit acceptance
+    pub gateway_fee_bps: u16,
+    /// Protocol fee the subscriber agreed to, increases need explicit acceptance
+    pub protocol_fee_bps: u16,
+    /// Policies created before fee snapshots existed lock in the live fees on
+    /// their next payment
+    pub has_fee_snapshot: bool,
+    /// Whether payments are capped by `allowance_remaining`
+    pub allowance_enabled: bool,
+    /// Budget left for this policy, topped up by the owner
+    pub allowance_remaining: u64,
+    /// Index of the next milestone to pay for `PolicyType::Milestone`
+    pub milestone_cursor: u8,
+    /// Whether each milestone must be approved by the owner before it is payable
+    pub milestone_approval_required: bool,
+    /// Number of milestones approved by the owner so far
+    pub milestones_approved: u8,
+    /// Whether missed periods are collected one per `execute_payment` call
+    /// instead of being recorded as arrears
+    pub catch_up: bool,
+    /// Number of periods that became due and were paid or recorded as arrears
+    pub periods_due: u32,
+    /// Number of missed periods that were skipped without being collected
+    pub arrears_periods: u32,
+    /// Failed payments reported since the policy became past due
+    pub failed_attempts: u8,
+    /// When the first of the `failed_attempts` was reported
+    pub first_failed_at: i64,
+    /// `payment_count` when the current subscription term started
+    pub term_start_payment_count: u32,
+    /// Number of times an auto-renewing subscription started a new term
+    pub renewal_count: u32,
+    pub padding: [u8; 212],
 }
 
 impl PaymentPolicy {
@@ -204,7 +463,7 @@ impl PaymentPolicy {
         32 + // user_payment: Pubkey
         32 + // recipient: Pubkey
         32 + // gateway: Pubkey
-        PolicyType::VARIANT_SIZE + // policy type size
+        PolicyType::TOTAL_SIZE + // policy type size including its tag
         1 + // status: PaymentStatus
         64 + // memo: [u8; 64]
         8 + // total_paid: u64
@@ -213,7 +472,225 @@ impl PaymentPolicy {
         8 + // updated_at: i64
         4 + // policy_id: u32
         1 + // bump: u8
-        256; // padding: [u8; 256]
+        2 + // gateway_fee_bps: u16
+        2 + // protocol_fee_bps: u16
+        1 + // has_fee_snapshot: bool
+        1 + // allowance_enabled: bool
+        8 + // allowance_remaining: u64
+        1 + // milestone_cursor: u8
+        1 + // milestone_approval_required: bool
+        1 + // milestones_approved: u8
+        1 + // catch_up: bool
+        4 + // periods_due: u32
+        4 + // arrears_periods: u32
+        1 + // failed_attempts: u8
+        8 + // first_failed_at: i64
+        4 + // term_start_payment_count: u32
+        4 + // renewal_count: u32
+        212; // padding: [u8; 212]
+
+    /// Store the given fees as the ones accepted by the subscriber
+    pub fn snapshot_fees(&mut self, gateway_fee_bps: u16, protocol_fee_bps: u16) {
+        self.gateway_fee_bps = gateway_fee_bps;
+        self.protocol_fee_bps = protocol_fee_bps;
+        self.has_fee_snapshot = true;
+    }
+
+    /// Returns the (gateway, protocol) fees to charge given the live fees.
+    /// Decreases are adopted right away, increases are capped at the snapshot.
+    pub fn effective_fees(&mut self, gateway_fee_bps: u16, protocol_fee_bps: u16) -> (u16, u16) {
+        if !self.has_fee_snapshot {
+            self.snapshot_fees(gateway_fee_bps, protocol_fee_bps);
+        }
+        self.gateway_fee_bps = self.gateway_fee_bps.min(gateway_fee_bps);
+        self.protocol_fee_bps = self.protocol_fee_bps.min(protocol_fee_bps);
+        (self.gateway_fee_bps, self.protocol_fee_bps)
+    }
+
+    /// Timestamp from which the next payment can be executed
+    pub fn next_payment_due(&self) -> i64 {
+        match &self.policy_type {
+            PolicyType::Subscription {
+                next_payment_due, ..
+            } => *next_payment_due,
+            PolicyType::Installment {
+                next_installment_due,
+                ..
+            } => *next_installment_due,
+            PolicyType::OneTime { due_date, .. } => *due_date,
+            PolicyType::Milestone { intervals, .. } => {
+                // Milestones are scheduled relative to the policy creation
+                let elapsed = intervals
+                    .iter()
+                    .take(self.milestone_cursor as usize + 1)
+                    .fold(0u64, |total, interval| total.saturating_add(*interval));
+                self.created_at
+                    .saturating_add(i64::try_from(elapsed).unwrap_or(i64::MAX))
+            }
+            PolicyType::Metered {
+                next_payment_due, ..
+            } => *next_payment_due,
+            PolicyType::Stream {
+                last_withdrawn_at, ..
+            } => *last_withdrawn_at,
+        }
+    }
+
+    /// Amount the next payment needs at least, metered policies use their cap
+    pub fn amount_due(&self) -> Result<u64> {
+        match &self.policy_type {
+            PolicyType::Subscription { amount, .. } => Ok(*amount),
+            PolicyType::Installment {
+                total_amount,
+                num_installments,
+                installment_amount,
+                installments_completed,
+                ..
+            } => Ok(PolicyType::installment_due_amount(
+                *total_amount,
+                *num_installments,
+                *installment_amount,
+                *installments_completed,
+            )),
+            PolicyType::OneTime { amount, .. } => Ok(*amount),
+            PolicyType::Milestone { milestones, .. } => Ok(milestones
+                .get(self.milestone_cursor as usize)
+                .copied()
+                .unwrap_or(0)),
+            PolicyType::Metered {
+                max_amount_per_period,
+                ..
+            } => Ok(*max_amount_per_period),
+            PolicyType::Stream { .. } => {
+                err!(crate::error::RecurringPaymentsError::InvalidPolicyType)
+            }
+        }
+    }
+
+    /// Whether the next milestone is still waiting for the owner's approval
+    pub fn milestone_awaiting_approval(&self) -> bool {
+        matches!(self.policy_type, PolicyType::Milestone { .. })
+            && self.milestone_approval_required
+            && self.milestone_cursor >= self.milestones_approved
+    }
+
+    /// Whether a subscription has hit its end date or can no longer charge
+    /// `amount` without exceeding its lifetime cap
+    pub fn subscription_limit_reached(&self, amount: u64, now: i64) -> bool {
+        match &self.policy_type {
+            PolicyType::Subscription {
+                max_total_amount,
+                end_timestamp,
+                ..
+            } => {
+                let past_end = *end_timestamp != 0 && now >= *end_timestamp;
+                let over_cap = *max_total_amount > 0
+                    && self.total_paid.saturating_add(amount) > *max_total_amount;
+                past_end || over_cap
+            }
+            _ => false,
+        }
+    }
+
+    /// Number of payments made in the current subscription term
+    pub fn term_payment_count(&self) -> u32 {
+        self.payment_count
+            .saturating_sub(self.term_start_payment_count)
+    }
+
+    /// Whether a subscription made all `max_renewals` payments of its current term
+    pub fn subscription_term_ended(&self) -> bool {
+        match &self.policy_type {
+            PolicyType::Subscription {
+                max_renewals: Some(max_renewals),
+                ..
+            } => self.term_payment_count() >= *max_renewals,
+            _ => false,
+        }
+    }
+
+    /// Whether a subscription ended its term without auto-renew and is finished
+    pub fn subscription_finished(&self) -> bool {
+        matches!(
+            self.policy_type,
+            PolicyType::Subscription {
+                auto_renew: false,
+                ..
+            }
+        ) && self.subscription_term_ended()
+    }
+
+    /// Whether a one-time payment was not collected before its grace period ended
+    pub fn grace_period_elapsed(&self, now: i64) -> bool {
+        match &self.policy_type {
+            PolicyType::OneTime {
+                due_date,
+                grace_period_seconds,
+                ..
+            } => {
+                let grace = i64::try_from(*grace_period_seconds).unwrap_or(i64::MAX);
+                now > due_date.saturating_add(grace)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether this is a stream that has not been stopped and fully withdrawn yet,
+    /// such a stream can only be ended through `stop_stream`
+    pub fn is_open_stream(&self) -> bool {
+        matches!(self.policy_type, PolicyType::Stream { .. }) && !self.status.is_terminal()
+    }
+
+    /// Returns the amount a stream accrued since the last withdrawal, capped at
+    /// the time the stream was stopped
+    pub fn stream_accrued(&self, now: i64) -> Result<u64> {
+        match &self.policy_type {
+            PolicyType::Stream {
+                rate_per_second,
+                last_withdrawn_at,
+                stopped_at,
+                ..
+            } => {
+                let accrued_until = stopped_at.map_or(now, |stopped| stopped.min(now));
+                let elapsed = accrued_until.saturating_sub(*last_withdrawn_at).max(0) as u64;
+                rate_per_second
+                    .checked_mul(elapsed)
+                    .ok_or(error!(crate::error::RecurringPaymentsError::MathOverflow))
+            }
+            _ => err!(crate::error::RecurringPaymentsError::InvalidPolicyType),
+        }
+    }
+
+    /// Returns the part of `amount` a stream pays out for whole seconds of
+    /// accrual and the timestamp the withdrawal checkpoint moves to
+    pub fn stream_withdrawal(&self, amount: u64) -> Result<(u64, i64)> {
+        match &self.policy_type {
+            PolicyType::Stream {
+                rate_per_second,
+                last_withdrawn_at,
+                ..
+            } => {
+                let seconds = amount / rate_per_second;
+                let withdrawn_until = i64::try_from(seconds)
+                    .ok()
+                    .and_then(|seconds| last_withdrawn_at.checked_add(seconds))
+                    .ok_or(crate::error::RecurringPaymentsError::MathOverflow)?;
+                Ok((seconds * rate_per_second, withdrawn_until))
+            }
+            _ => err!(crate::error::RecurringPaymentsError::InvalidPolicyType),
+        }
+    }
+
+    /// Deducts `amount` from the policy budget when one is set
+    pub fn spend_allowance(&mut self, amount: u64) -> Result<()> {
+        if self.allowance_enabled {
+            self.allowance_remaining = self
+                .allowance_remaining
+                .checked_sub(amount)
+                .ok_or(crate::error::RecurringPaymentsError::PolicyAllowanceExceeded)?;
+        }
+        Ok(())
+    }
 }
 
 /// This is a unique global program configuration managed by an admin that
@@ -226,7 +703,16 @@ pub struct ProgramConfig {
     pub max_policies_per_user: u32,
     pub emergency_pause: bool,
     pub bump: u8,
-    pub padding: [u8; 256],
+    /// Optional key that may pause (but not unpause) the program.
+    /// `Pubkey::default()` means no guardian is set.
+    pub guardian: Pubkey,
+    /// Proposed new admin that still has to accept the role.
+    /// `Pubkey::default()` means no transfer is pending.
+    pub pending_admin: Pubkey,
+    /// Upper bound for `PaymentGateway.gateway_fee_bps`, only configs created
+    /// before the cap existed hold 0 and use the default
+    pub max_gateway_fee_bps: u16,
+    pub padding: [u8; 190],
 }
 
 impl ProgramConfig {
@@ -237,7 +723,37 @@ impl ProgramConfig {
         4 + // max_policies_per_user: u32
         1 + // emergency_pause: bool
         1 + // bump: u8
-        256; // padding: [u8; 256]
+        32 + // guardian: Pubkey
+        32 + // pending_admin: Pubkey
+        2 + // max_gateway_fee_bps: u16
+        190; // padding: [u8; 190]
+
+    /// Returns the gateway fee cap, falling back to the default for configs
+    /// created before the cap existed
+    pub fn gateway_fee_cap(&self) -> u16 {
+        match self.max_gateway_fee_bps {
+            0 => DEFAULT_MAX_GATEWAY_FEE_BPS,
+            cap => cap,
+        }
+    }
+
+    /// Validates a gateway fee against the configured bounds
+    pub fn validate_gateway_fee(&self, gateway_fee_bps: u16) -> Result<()> {
+        require!(
+            gateway_fee_bps <= self.gateway_fee_cap(),
+            crate::error::RecurringPaymentsError::GatewayFeeTooHigh
+        );
+        require!(
+            gateway_fee_bps as u64 + self.protocol_fee_bps as u64 <= crate::BPS_DENOMINATOR,
+            crate::error::RecurringPaymentsError::FeesExceedAmount
+        );
+        Ok(())
+    }
+
+    /// Returns true if the given key is the configured guardian
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        self.guardian != Pubkey::default() && self.guardian == *key
+    }
 }
 
 /// An event that is thrown when a payment takes place
@@ -249,6 +765,22 @@ pub struct PaymentRecord {
     pub timestamp: i64,
     pub memo: [u8; 64],
     pub record_id: u32,
+    /// Total withheld by the mint's transfer fee extension across all legs
+    pub transfer_fee: u64,
+    /// Usage reported by the gateway for metered policies
+    pub usage_amount: Option<u64>,
+    /// Index of the period this payment settles, starting at 1
+    pub period: u32,
+}
+
+/// An event that is thrown after a payment with the allowance left on the user's delegate
+#[event]
+pub struct DelegatedAllowance {
+    pub payment_policy: Pubkey,
+    pub user_payment: Pubkey,
+    pub gateway: Pubkey,
+    pub remaining_allowance: u64,
+    pub periods_remaining: u64,
 }
 
 /// An event that is thrown when the program is initialized
@@ -257,9 +789,55 @@ pub struct ProgramConfigCreated {
     pub admin: Pubkey,
     pub fee_recipient: Pubkey,
     pub protocol_fee_bps: u16,
+    pub max_gateway_fee_bps: u16,
     pub max_policies_per_user: u32,
 }
 
+/// An event that is thrown when the program configuration is updated
+#[event]
+pub struct ProgramConfigUpdated {
+    pub admin: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub protocol_fee_bps: u16,
+    pub max_gateway_fee_bps: u16,
+    pub max_policies_per_user: u32,
+}
+
+/// An event that is thrown when an admin transfer is proposed
+#[event]
+pub struct AdminTransferProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+/// An event that is thrown when the pending admin accepts the role
+#[event]
+pub struct AdminTransferred {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+/// An event that is thrown when the guardian is changed
+#[event]
+pub struct GuardianChanged {
+    pub old_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+}
+
+/// An event that is thrown when the program is paused
+#[event]
+pub struct ProgramPaused {
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+/// An event that is thrown when the program is unpaused
+#[event]
+pub struct ProgramUnpaused {
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
 /// An event that is thrown when a user payment account is created
 #[event]
 pub struct UserPaymentCreated {
@@ -278,6 +856,35 @@ pub struct PaymentGatewayCreated {
     pub url: [u8; 64],
 }
 
+/// An event that is thrown when a payment gateway is updated by its authority
+#[event]
+pub struct PaymentGatewayUpdated {
+    pub gateway: Pubkey,
+    pub old_fee_recipient: Pubkey,
+    pub new_fee_recipient: Pubkey,
+    pub old_gateway_fee_bps: u16,
+    pub new_gateway_fee_bps: u16,
+    pub old_name: [u8; 32],
+    pub new_name: [u8; 32],
+    pub old_url: [u8; 64],
+    pub new_url: [u8; 64],
+}
+
+/// An event that is thrown when a payment gateway is suspended or reinstated
+#[event]
+pub struct PaymentGatewayStatusChanged {
+    pub gateway: Pubkey,
+    pub changed_by: Pubkey,
+    pub is_active: bool,
+}
+
+/// An event that is thrown when a payment gateway is retired in favour of a successor
+#[event]
+pub struct PaymentGatewayRetired {
+    pub gateway: Pubkey,
+    pub successor: Pubkey,
+}
+
 /// An event that is thrown when a payment policy is created
 #[event]
 pub struct PaymentPolicyCreated {
@@ -305,6 +912,106 @@ pub struct PaymentPolicyStatusChanged {
     pub new_status: PaymentStatus,
 }
 
+/// An event that is thrown when a payment policy is moved to another gateway
+#[event]
+pub struct PaymentPolicyGatewayChanged {
+    pub payment_policy: Pubkey,
+    pub old_gateway: Pubkey,
+    pub new_gateway: Pubkey,
+}
+
+/// An event that is thrown when a user moves an approval to their own delegate
+#[event]
+pub struct DelegateMigrated {
+    pub user_payment: Pubkey,
+    pub old_delegate: Pubkey,
+    pub new_delegate: Pubkey,
+    pub amount: u64,
+}
+
+/// An event that is thrown when a user adds budget to a payment policy
+#[event]
+pub struct PolicyAllowanceToppedUp {
+    pub payment_policy: Pubkey,
+    pub amount: u64,
+    pub allowance_remaining: u64,
+}
+
+/// An event that is thrown when the milestone approval mode of a policy changes
+#[event]
+pub struct MilestoneApprovalModeChanged {
+    pub payment_policy: Pubkey,
+    pub approval_required: bool,
+}
+
+/// An event that is thrown when the owner approves a milestone for payment
+#[event]
+pub struct MilestoneApproved {
+    pub payment_policy: Pubkey,
+    pub milestone_index: u8,
+}
+
+/// An event that is thrown when the payer stops a stream
+#[event]
+pub struct StreamStopped {
+    pub payment_policy: Pubkey,
+    pub stopped_at: i64,
+}
+
+/// An event that is thrown when the gateway reports a failed payment. Merchants
+/// revoke access once `status` is `Suspended`.
+#[event]
+pub struct PaymentFailed {
+    pub payment_policy: Pubkey,
+    pub gateway: Pubkey,
+    pub failed_attempts: u8,
+    pub first_failed_at: i64,
+    pub status: PaymentStatus,
+    pub timestamp: i64,
+}
+
+/// An event that is thrown when a gateway changes its dunning settings
+#[event]
+pub struct GatewayDunningUpdated {
+    pub gateway: Pubkey,
+    pub max_payment_retries: u8,
+    pub retry_window_seconds: i64,
+}
+
+/// An event that is thrown when the catch-up setting of a policy changes
+#[event]
+pub struct PolicyCatchUpChanged {
+    pub payment_policy: Pubkey,
+    pub catch_up: bool,
+}
+
+/// An event that is thrown when an auto-renewing subscription starts a new term
+#[event]
+pub struct SubscriptionRenewed {
+    pub payment_policy: Pubkey,
+    pub renewal_count: u32,
+    pub next_payment_due: i64,
+    pub timestamp: i64,
+}
+
+/// An event that is thrown when the auto-renew setting of a subscription changes
+#[event]
+pub struct AutoRenewChanged {
+    pub payment_policy: Pubkey,
+    pub auto_renew: bool,
+}
+
+/// An event that is thrown when a subscriber accepts the current fees of a policy
+#[event]
+pub struct PolicyFeesAccepted {
+    pub payment_policy: Pubkey,
+    pub gateway: Pubkey,
+    pub old_gateway_fee_bps: u16,
+    pub new_gateway_fee_bps: u16,
+    pub old_protocol_fee_bps: u16,
+    pub new_protocol_fee_bps: u16,
+}
+
 /// An event that is thrown when a payment policy is deleted
 #[event]
 pub struct PaymentPolicyDeleted {
@@ -320,3 +1027,149 @@ pub struct PaymentGatewayDeleted {
     pub authority: Pubkey,
     pub name: [u8; 32],
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy_with(policy_type: PolicyType) -> PaymentPolicy {
+        PaymentPolicy {
+            user_payment: Pubkey::default(),
+            recipient: Pubkey::default(),
+            gateway: Pubkey::default(),
+            policy_type,
+            status: PaymentStatus::Active,
+            memo: [0; 64],
+            total_paid: 0,
+            payment_count: 0,
+            created_at: 0,
+            updated_at: 0,
+            policy_id: 0,
+            bump: 0,
+            gateway_fee_bps: 0,
+            protocol_fee_bps: 0,
+            has_fee_snapshot: false,
+            allowance_enabled: false,
+            allowance_remaining: 0,
+            milestone_cursor: 0,
+            milestone_approval_required: false,
+            milestones_approved: 0,
+            catch_up: false,
+            periods_due: 0,
+            arrears_periods: 0,
+            failed_attempts: 0,
+            first_failed_at: 0,
+            term_start_payment_count: 0,
+            renewal_count: 0,
+            padding: [0; 212],
+        }
+    }
+
+    #[test]
+    fn largest_policy_types_fit_the_account() {
+        // Existing accounts and the SDK filters rely on this size
+        assert_eq!(PaymentPolicy::SIZE, 586);
+
+        let largest = [
+            PolicyType::Subscription {
+                amount: u64::MAX,
+                auto_renew: true,
+                max_renewals: Some(u32::MAX),
+                payment_frequency: PaymentFrequency::Custom(u64::MAX),
+                next_payment_due: i64::MAX,
+                max_total_amount: u64::MAX,
+                end_timestamp: i64::MAX,
+                padding: [0; 81],
+            },
+            PolicyType::Installment {
+                total_amount: u64::MAX,
+                num_installments: u32::MAX,
+                installment_amount: u64::MAX,
+                period: PaymentFrequency::Custom(u64::MAX),
+                start_date: i64::MAX,
+                next_installment_due: i64::MAX,
+                installments_completed: u32::MAX,
+                padding: [0; 79],
+            },
+            PolicyType::OneTime {
+                amount: u64::MAX,
+                due_date: i64::MAX,
+                grace_period_seconds: u64::MAX,
+                padding: [0; 104],
+            },
+            PolicyType::Milestone {
+                milestones: [u64::MAX; 8],
+                intervals: [u64::MAX; 8],
+            },
+            PolicyType::Metered {
+                max_amount_per_period: u64::MAX,
+                payment_frequency: PaymentFrequency::Custom(u64::MAX),
+                next_payment_due: i64::MAX,
+                padding: [0; 103],
+            },
+            PolicyType::Stream {
+                rate_per_second: u64::MAX,
+                start_time: i64::MAX,
+                last_withdrawn_at: i64::MAX,
+                stopped_at: Some(i64::MAX),
+                padding: [0; 95],
+            },
+        ];
+
+        for policy_type in largest {
+            assert_eq!(
+                policy_type.try_to_vec().unwrap().len(),
+                PolicyType::TOTAL_SIZE
+            );
+            let policy = policy_with(policy_type);
+            assert_eq!(8 + policy.try_to_vec().unwrap().len(), PaymentPolicy::SIZE);
+        }
+    }
+
+    #[test]
+    fn stopped_stream_fits_the_account_and_stops_accruing() {
+        let mut policy = policy_with(PolicyType::Stream {
+            rate_per_second: 1000,
+            start_time: 100,
+            last_withdrawn_at: 100,
+            stopped_at: None,
+            padding: [0; 95],
+        });
+
+        // Same update as stop_stream, the account must still serialize in place
+        if let PolicyType::Stream { stopped_at, .. } = &mut policy.policy_type {
+            *stopped_at = Some(110);
+        }
+        assert_eq!(8 + policy.try_to_vec().unwrap().len(), PaymentPolicy::SIZE);
+
+        assert_eq!(policy.stream_accrued(105).unwrap(), 5000);
+        assert_eq!(policy.stream_accrued(200).unwrap(), 10000);
+        assert_eq!(policy.stream_withdrawal(10000).unwrap(), (10000, 110));
+    }
+
+    #[test]
+    fn legacy_config_uses_the_default_gateway_fee_cap() {
+        let mut config = ProgramConfig {
+            admin: Pubkey::default(),
+            fee_recipient: Pubkey::default(),
+            protocol_fee_bps: 50,
+            max_policies_per_user: 20,
+            emergency_pause: false,
+            bump: 0,
+            guardian: Pubkey::default(),
+            pending_admin: Pubkey::default(),
+            max_gateway_fee_bps: 0,
+            padding: [0; 190],
+        };
+        assert_eq!(config.gateway_fee_cap(), DEFAULT_MAX_GATEWAY_FEE_BPS);
+        assert!(config
+            .validate_gateway_fee(DEFAULT_MAX_GATEWAY_FEE_BPS)
+            .is_ok());
+        assert!(config
+            .validate_gateway_fee(DEFAULT_MAX_GATEWAY_FEE_BPS + 1)
+            .is_err());
+
+        config.max_gateway_fee_bps = 100;
+        assert_eq!(config.gateway_fee_cap(), 100);
+    }
+}
@@ -1,59 +1,199 @@
-use crate::PaymentFrequency;
+use crate::{error::RecurringPaymentsError, PaymentFrequency, BPS_DENOMINATOR};
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        },
+        onchain::{invoke_transfer_checked, invoke_transfer_checked_with_fee},
+    },
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
 
-/// Calculate the next payment due date based on payment frequency
+/// Returns the transfer fee the mint withholds on `amount`, `None` for mints
+/// without the Token-2022 transfer fee extension
+pub fn get_transfer_fee(mint_info: &AccountInfo, amount: u64) -> Result<Option<u64>> {
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(None);
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => {
+            let fee = transfer_fee_config
+                .calculate_epoch_fee(Clock::get()?.epoch, amount)
+                .ok_or(RecurringPaymentsError::MathOverflow)?;
+            Ok(Some(fee))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Transfer `amount` out of the user's token account, signed by the payments delegate.
+/// Returns the fee withheld by the mint. Mints with a transfer fee go through
+/// `transfer_checked_with_fee` so the withheld fee always matches our accounting.
+///
+/// `additional_accounts` must hold the accounts required by the mint's transfer
+/// hook (hook program, validation account and resolved extra accounts), if any.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_from_user<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    additional_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    match get_transfer_fee(&mint_info, amount)? {
+        Some(fee) => {
+            invoke_transfer_checked_with_fee(
+                token_program.key,
+                from.to_account_info(),
+                mint_info,
+                to.to_account_info(),
+                authority.clone(),
+                additional_accounts,
+                amount,
+                mint.decimals,
+                fee,
+                signer_seeds,
+            )?;
+            Ok(fee)
+        }
+        None => {
+            invoke_transfer_checked(
+                token_program.key,
+                from.to_account_info(),
+                mint_info,
+                to.to_account_info(),
+                authority.clone(),
+                additional_accounts,
+                amount,
+                mint.decimals,
+                signer_seeds,
+            )?;
+            Ok(0)
+        }
+    }
+}
+
+/// Transfer the (recipient_amount, gateway_fee, protocol_fee) shares of a
+/// payment out of the user's token account. Returns the total fee withheld by
+/// the mint.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_payment<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    recipient_account: &InterfaceAccount<'info, TokenAccount>,
+    gateway_fee_account: &InterfaceAccount<'info, TokenAccount>,
+    protocol_fee_account: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    additional_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+    (recipient_amount, gateway_fee, protocol_fee): (u64, u64, u64),
+) -> Result<u64> {
+    // Each leg is charged the mint's transfer fee (Token-2022) on its own share,
+    // so recipient, gateway and protocol bear it proportionally
+    let mut transfer_fee: u64 = 0;
+    for (to, amount) in [
+        (recipient_account, recipient_amount),
+        (gateway_fee_account, gateway_fee),
+        (protocol_fee_account, protocol_fee),
+    ] {
+        if amount > 0 {
+            transfer_fee += transfer_from_user(
+                token_program,
+                from,
+                mint,
+                to,
+                authority,
+                additional_accounts,
+                signer_seeds,
+                amount,
+            )?;
+        }
+    }
+    Ok(transfer_fee)
+}
+
+/// Split a payment amount into (recipient_amount, gateway_fee, protocol_fee)
+pub fn calculate_fees(
+    amount: u64,
+    gateway_fee_bps: u16,
+    protocol_fee_bps: u16,
+) -> Result<(u64, u64, u64)> {
+    require!(
+        gateway_fee_bps as u64 + protocol_fee_bps as u64 <= BPS_DENOMINATOR,
+        RecurringPaymentsError::FeesExceedAmount
+    );
+
+    let gateway_fee = (amount as u128)
+        .checked_mul(gateway_fee_bps as u128)
+        .ok_or(RecurringPaymentsError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+
+    let protocol_fee = (amount as u128)
+        .checked_mul(protocol_fee_bps as u128)
+        .ok_or(RecurringPaymentsError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+
+    let recipient_amount = (amount as u128)
+        .checked_sub(gateway_fee)
+        .and_then(|v| v.checked_sub(protocol_fee))
+        .ok_or(RecurringPaymentsError::FeesExceedAmount)?;
+
+    // All parts are bounded by `amount` and therefore fit into u64
+    Ok((
+        recipient_amount as u64,
+        gateway_fee as u64,
+        protocol_fee as u64,
+    ))
+}
+
+/// Calculate the next payment due date based on payment frequency. Returns the
+/// first due date after `current_timestamp` and the number of periods advanced.
 pub fn calculate_next_payment_due(
     current_due: i64,
     frequency: &PaymentFrequency,
     current_timestamp: i64,
-) -> Result<i64> {
+) -> Result<(i64, u32)> {
     let mut next_due = current_due;
+    let mut periods: u32 = 0;
 
-    match frequency {
-        PaymentFrequency::Daily => {
-            // Add 24 hours (86400 seconds)
-            while next_due <= current_timestamp {
-                next_due += 86400;
-            }
-        }
-        PaymentFrequency::Weekly => {
-            // Add 7 days (604800 seconds)
-            while next_due <= current_timestamp {
-                next_due += 604800;
-            }
-        }
-        PaymentFrequency::Monthly => {
-            // Add one month, maintaining the same day
-            while next_due <= current_timestamp {
-                next_due = add_months(next_due, 1)?;
-            }
-        }
-        PaymentFrequency::Quarterly => {
-            // Add 3 months, maintaining the same day
-            while next_due <= current_timestamp {
-                next_due = add_months(next_due, 3)?;
-            }
-        }
-        PaymentFrequency::SemiAnnually => {
-            // Add 6 months, maintaining the same day
-            while next_due <= current_timestamp {
-                next_due = add_months(next_due, 6)?;
-            }
-        }
-        PaymentFrequency::Annually => {
-            // Add 12 months, maintaining the same day
-            while next_due <= current_timestamp {
-                next_due = add_months(next_due, 12)?;
-            }
-        }
-        PaymentFrequency::Custom(interval_seconds) => {
-            // Add custom interval in seconds
-            while next_due <= current_timestamp {
-                next_due += *interval_seconds as i64;
-            }
-        }
+    while next_due <= current_timestamp {
+        next_due = next_period_due(next_due, frequency)?;
+        periods = periods
+            .checked_add(1)
+            .ok_or(RecurringPaymentsError::MathOverflow)?;
     }
 
+    Ok((next_due, periods))
+}
+
+/// Advance a due date by exactly one period of the payment frequency
+pub fn next_period_due(current_due: i64, frequency: &PaymentFrequency) -> Result<i64> {
+    let next_due = match frequency {
+        // Add 24 hours (86400 seconds)
+        PaymentFrequency::Daily => current_due + 86400,
+        // Add 7 days (604800 seconds)
+        PaymentFrequency::Weekly => current_due + 604800,
+        // Add one month, maintaining the same day
+        PaymentFrequency::Monthly => add_months(current_due, 1)?,
+        // Add 3 months, maintaining the same day
+        PaymentFrequency::Quarterly => add_months(current_due, 3)?,
+        // Add 6 months, maintaining the same day
+        PaymentFrequency::SemiAnnually => add_months(current_due, 6)?,
+        // Add 12 months, maintaining the same day
+        PaymentFrequency::Annually => add_months(current_due, 12)?,
+        // Add custom interval in seconds
+        PaymentFrequency::Custom(interval_seconds) => current_due + *interval_seconds as i64,
+    };
+
     Ok(next_due)
 }
 
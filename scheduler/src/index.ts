@@ -125,8 +125,15 @@ class PaymentScheduler {
         return false;
       }
 
+      // Auto-renewing subscriptions start a new term instead of finishing
       const maxRenewals = subscriptionDetails.maxRenewals;
-      if (maxRenewals !== null && policy.paymentCount >= maxRenewals) {
+      const termPaymentCount =
+        policy.paymentCount - policy.termStartPaymentCount;
+      if (
+        maxRenewals !== null &&
+        !subscriptionDetails.autoRenew &&
+        termPaymentCount >= maxRenewals
+      ) {
         console.log(
           `Policy ${policy.policyId} has reached max renewals (${maxRenewals})`
         );
@@ -1,5 +1,6 @@
 export const PROTOCOL_FEE_BPS = 100;
 export const MAX_POLICIES_PER_USER = 10;
+export const MAX_GATEWAY_FEE_BPS = 1000;
 export const TOKEN_DECIMALS = 6;
 
 export const SEEDS = {
@@ -51,7 +51,19 @@ export function getPaymentPolicyPda(
   return { address, bump };
 }
 
-export function getPaymentsDelegatePda(programId: PublicKey): PdaResult {
+export function getPaymentsDelegatePda(
+  userPayment: PublicKey,
+  programId: PublicKey
+): PdaResult {
+  const [address, bump] = PublicKey.findProgramAddressSync(
+    [Buffer.from(SEEDS.PAYMENTS), userPayment.toBuffer()],
+    programId
+  );
+  return { address, bump };
+}
+
+// Program-wide delegate used before delegates were derived per user payment
+export function getLegacyPaymentsDelegatePda(programId: PublicKey): PdaResult {
   const [address, bump] = PublicKey.findProgramAddressSync(
     [Buffer.from(SEEDS.PAYMENTS)],
     programId
@@ -10,6 +10,10 @@ import {
   createApproveInstruction,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  getMint,
+  getTransferHook,
+  createTransferCheckedWithTransferHookInstruction,
 } from "@solana/spl-token";
 import * as anchor from "@coral-xyz/anchor";
 import {
@@ -18,6 +22,7 @@ import {
   getUserPaymentPda,
   getPaymentPolicyPda,
   getPaymentsDelegatePda,
+  getLegacyPaymentsDelegatePda,
 } from "./pda";
 import type {
   PolicyType,
@@ -27,6 +32,7 @@ import type {
   PaymentGateway,
   ProgramConfig,
 } from "./types.js";
+import { encodeMemo } from "./utils";
 import IDL from "../../target/idl/recurring_payments.json"; // with { type: "json" };
 import { RecurringPayments } from "../../target/types/recurring_payments.js";
 
@@ -68,6 +74,80 @@ export class Tributary {
       .instruction();
   }
 
+  async updateProgramConfig(
+    protocolFeeBps: number,
+    feeRecipient: PublicKey,
+    maxGatewayFeeBps: number,
+    maxPoliciesPerUser: number
+  ): Promise<TransactionInstruction> {
+    const admin = this.provider.publicKey;
+    const { address: configPda } = getConfigPda(this.programId);
+
+    return await this.program.methods
+      .updateProgramConfig(protocolFeeBps, maxGatewayFeeBps, maxPoliciesPerUser)
+      .accountsStrict({
+        admin,
+        config: configPda,
+        feeRecipient,
+      })
+      .instruction();
+  }
+
+  async proposeAdmin(newAdmin: PublicKey): Promise<TransactionInstruction> {
+    const admin = this.provider.publicKey;
+    const { address: configPda } = getConfigPda(this.programId);
+
+    return await this.program.methods
+      .proposeAdmin()
+      .accountsStrict({
+        admin,
+        config: configPda,
+        newAdmin,
+      })
+      .instruction();
+  }
+
+  async acceptAdmin(): Promise<TransactionInstruction> {
+    const newAdmin = this.provider.publicKey;
+    const { address: configPda } = getConfigPda(this.programId);
+
+    return await this.program.methods
+      .acceptAdmin()
+      .accountsStrict({
+        newAdmin,
+        config: configPda,
+      })
+      .instruction();
+  }
+
+  async setGuardian(
+    guardian: PublicKey | null
+  ): Promise<TransactionInstruction> {
+    const admin = this.provider.publicKey;
+    const { address: configPda } = getConfigPda(this.programId);
+
+    return await this.program.methods
+      .setGuardian(guardian)
+      .accountsStrict({
+        admin,
+        config: configPda,
+      })
+      .instruction();
+  }
+
+  async setEmergencyPause(paused: boolean): Promise<TransactionInstruction> {
+    const authority = this.provider.publicKey;
+    const { address: configPda } = getConfigPda(this.programId);
+
+    return await this.program.methods
+      .setEmergencyPause(paused)
+      .accountsStrict({
+        authority,
+        config: configPda,
+      })
+      .instruction();
+  }
+
   async createUserPayment(
     tokenMint: PublicKey
   ): Promise<TransactionInstruction> {
@@ -77,10 +157,16 @@ export class Tributary {
       tokenMint
     );
     const { address: configPda } = getConfigPda(this.programId);
+    const tokenProgram = await this.getTokenProgramId(tokenMint);
     const accounts = {
       owner: owner,
       config: configPda,
-      tokenAccount: getAssociatedTokenAddressSync(tokenMint, owner),
+      tokenAccount: getAssociatedTokenAddressSync(
+        tokenMint,
+        owner,
+        false,
+        tokenProgram
+      ),
       tokenMint: tokenMint,
       userPayment: userPaymentPda,
       systemProgram: SystemProgram.programId,
@@ -130,6 +216,103 @@ export class Tributary {
       .instruction();
   }
 
+  async updatePaymentGateway(
+    gatewayFeeBps: number,
+    gatewayFeeRecipient: PublicKey,
+    name: string,
+    url: string
+  ): Promise<TransactionInstruction> {
+    const authority = this.provider.publicKey;
+    const { address: gatewayPda } = this.getGatewayPda(authority);
+    const { address: configPda } = getConfigPda(this.programId);
+
+    const accounts = {
+      authority,
+      gateway: gatewayPda,
+      config: configPda,
+      feeRecipient: gatewayFeeRecipient,
+    };
+    return await this.program.methods
+      .updatePaymentGateway(
+        gatewayFeeBps,
+        encodeMemo(name, 32),
+        encodeMemo(url, 64)
+      )
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async setGatewayActive(
+    gateway: PublicKey,
+    active: boolean
+  ): Promise<TransactionInstruction> {
+    const authority = this.provider.publicKey;
+    const { address: configPda } = getConfigPda(this.programId);
+
+    return await this.program.methods
+      .setGatewayActive(active)
+      .accountsStrict({
+        authority,
+        gateway,
+        config: configPda,
+      })
+      .instruction();
+  }
+
+  async retirePaymentGateway(
+    gateway: PublicKey,
+    successorGateway: PublicKey
+  ): Promise<TransactionInstruction> {
+    const admin = this.provider.publicKey;
+    const { address: configPda } = getConfigPda(this.programId);
+
+    return await this.program.methods
+      .retirePaymentGateway()
+      .accountsStrict({
+        admin,
+        gateway,
+        successorGateway,
+        config: configPda,
+      })
+      .instruction();
+  }
+
+  async rebindPolicy(
+    paymentPolicyPda: PublicKey
+  ): Promise<TransactionInstruction> {
+    const paymentPolicy = await this.program.account.paymentPolicy.fetch(
+      paymentPolicyPda
+    );
+    const retiredGateway = await this.program.account.paymentGateway.fetch(
+      paymentPolicy.gateway
+    );
+
+    return await this.program.methods
+      .rebindPolicy()
+      .accountsStrict({
+        paymentPolicy: paymentPolicyPda,
+        retiredGateway: paymentPolicy.gateway,
+        successorGateway: retiredGateway.successor,
+      })
+      .instruction();
+  }
+
+  async expirePaymentPolicy(
+    paymentPolicyPda: PublicKey
+  ): Promise<TransactionInstruction> {
+    const paymentPolicy = await this.program.account.paymentPolicy.fetch(
+      paymentPolicyPda
+    );
+
+    return await this.program.methods
+      .expirePaymentPolicy()
+      .accountsStrict({
+        paymentPolicy: paymentPolicyPda,
+        userPayment: paymentPolicy.userPayment,
+      })
+      .instruction();
+  }
+
   async createPaymentPolicy(
     tokenMint: PublicKey,
     recipient: PublicKey,
@@ -139,17 +322,11 @@ export class Tributary {
     maxRenewals: number | null,
     paymentFrequency: PaymentFrequency,
     memo: number[],
-    startTime?: anchor.BN | null
+    startTime?: anchor.BN | null,
+    maxTotalAmount?: anchor.BN | null,
+    endTimestamp?: anchor.BN | null
   ): Promise<TransactionInstruction> {
-    const user = this.provider.publicKey;
-    const { address: userPaymentPda } = this.getUserPaymentPda(user, tokenMint);
-    const userPayment: UserPayment | null =
-      await this.program.account.userPayment.fetchNullable(userPaymentPda);
-    let policyId: number = 1;
-    if (userPayment) {
-      policyId = userPayment.activePoliciesCount + 1;
-    }
-    const paymentPolicy = this.getPaymentPolicyPda(userPaymentPda, policyId);
+    const policyId = await this.getNextPolicyId(tokenMint);
     const nextPaymentDue =
       startTime || new anchor.BN(Math.floor(Date.now() / 1000));
     const policyType: PolicyType = {
@@ -159,9 +336,209 @@ export class Tributary {
         maxRenewals: maxRenewals,
         paymentFrequency: paymentFrequency,
         nextPaymentDue: nextPaymentDue,
-        padding: new Array(97).fill(0),
+        maxTotalAmount: maxTotalAmount ?? new anchor.BN(0),
+        endTimestamp: endTimestamp ?? new anchor.BN(0),
+        padding: new Array(81).fill(0),
+      },
+    };
+    return await this.createPolicyInstruction(
+      tokenMint,
+      recipient,
+      gateway,
+      policyId,
+      policyType,
+      memo
+    );
+  }
+
+  // Split totalAmount into numInstallments payments, the last one absorbs the remainder
+  async createInstallmentPolicy(
+    tokenMint: PublicKey,
+    recipient: PublicKey,
+    gateway: PublicKey,
+    totalAmount: anchor.BN,
+    numInstallments: number,
+    period: PaymentFrequency,
+    memo: number[],
+    startTime?: anchor.BN | null
+  ): Promise<TransactionInstruction> {
+    const policyId = await this.getNextPolicyId(tokenMint);
+    const startDate =
+      startTime || new anchor.BN(Math.floor(Date.now() / 1000));
+    const policyType: PolicyType = {
+      installment: {
+        totalAmount: totalAmount,
+        numInstallments: numInstallments,
+        installmentAmount: totalAmount.divn(numInstallments),
+        period: period,
+        startDate: startDate,
+        nextInstallmentDue: startDate,
+        installmentsCompleted: 0,
+        padding: new Array(79).fill(0),
+      },
+    };
+    return await this.createPolicyInstruction(
+      tokenMint,
+      recipient,
+      gateway,
+      policyId,
+      policyType,
+      memo
+    );
+  }
+
+  // Invoice collectable once between dueDate and dueDate + gracePeriodSeconds
+  async createOneTimePolicy(
+    tokenMint: PublicKey,
+    recipient: PublicKey,
+    gateway: PublicKey,
+    amount: anchor.BN,
+    dueDate: anchor.BN,
+    gracePeriodSeconds: anchor.BN,
+    memo: number[]
+  ): Promise<TransactionInstruction> {
+    const policyId = await this.getNextPolicyId(tokenMint);
+    const policyType: PolicyType = {
+      oneTime: {
+        amount: amount,
+        dueDate: dueDate,
+        gracePeriodSeconds: gracePeriodSeconds,
+        padding: new Array(104).fill(0),
+      },
+    };
+    return await this.createPolicyInstruction(
+      tokenMint,
+      recipient,
+      gateway,
+      policyId,
+      policyType,
+      memo
+    );
+  }
+
+  // Charge the usage reported by the gateway each period, up to maxAmountPerPeriod
+  async createMeteredPolicy(
+    tokenMint: PublicKey,
+    recipient: PublicKey,
+    gateway: PublicKey,
+    maxAmountPerPeriod: anchor.BN,
+    paymentFrequency: PaymentFrequency,
+    memo: number[],
+    startTime?: anchor.BN | null
+  ): Promise<TransactionInstruction> {
+    const policyId = await this.getNextPolicyId(tokenMint);
+    const nextPaymentDue =
+      startTime || new anchor.BN(Math.floor(Date.now() / 1000));
+    const policyType: PolicyType = {
+      metered: {
+        maxAmountPerPeriod: maxAmountPerPeriod,
+        paymentFrequency: paymentFrequency,
+        nextPaymentDue: nextPaymentDue,
+        padding: new Array(103).fill(0),
+      },
+    };
+    return await this.createPolicyInstruction(
+      tokenMint,
+      recipient,
+      gateway,
+      policyId,
+      policyType,
+      memo
+    );
+  }
+
+  // Accrue ratePerSecond every second from startTime until stopped
+  async createStreamPolicy(
+    tokenMint: PublicKey,
+    recipient: PublicKey,
+    gateway: PublicKey,
+    ratePerSecond: anchor.BN,
+    memo: number[],
+    startTime?: anchor.BN | null
+  ): Promise<TransactionInstruction> {
+    const policyId = await this.getNextPolicyId(tokenMint);
+    // Streams cannot start before the on-chain clock, so default to it
+    const start =
+      startTime ||
+      new anchor.BN(
+        (await this.connection.getBlockTime(
+          await this.connection.getSlot()
+        )) ?? Math.floor(Date.now() / 1000)
+      );
+    const policyType: PolicyType = {
+      stream: {
+        ratePerSecond: ratePerSecond,
+        startTime: start,
+        lastWithdrawnAt: start,
+        stoppedAt: null,
+        padding: new Array(95).fill(0),
+      },
+    };
+    return await this.createPolicyInstruction(
+      tokenMint,
+      recipient,
+      gateway,
+      policyId,
+      policyType,
+      memo
+    );
+  }
+
+  // Pay up to 8 milestone amounts, each intervals[i] seconds after the previous one
+  async createMilestonePolicy(
+    tokenMint: PublicKey,
+    recipient: PublicKey,
+    gateway: PublicKey,
+    milestones: anchor.BN[],
+    intervals: anchor.BN[],
+    memo: number[]
+  ): Promise<TransactionInstruction> {
+    const policyId = await this.getNextPolicyId(tokenMint);
+    const pad = (values: anchor.BN[]) =>
+      values.concat(
+        Array.from({ length: 8 - values.length }, () => new anchor.BN(0))
+      );
+    const policyType: PolicyType = {
+      milestone: {
+        milestones: pad(milestones),
+        intervals: pad(intervals),
       },
     };
+    return await this.createPolicyInstruction(
+      tokenMint,
+      recipient,
+      gateway,
+      policyId,
+      policyType,
+      memo
+    );
+  }
+
+  // Next free policy ID for the current wallet and mint
+  async getNextPolicyId(tokenMint: PublicKey): Promise<number> {
+    const user = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(user, tokenMint);
+    const userPayment: UserPayment | null =
+      await this.program.account.userPayment.fetchNullable(userPaymentPda);
+    if (!userPayment) {
+      return 1;
+    }
+    return (
+      Math.max(userPayment.lastPolicyId, userPayment.activePoliciesCount) + 1
+    );
+  }
+
+  async createPolicyInstruction(
+    tokenMint: PublicKey,
+    recipient: PublicKey,
+    gateway: PublicKey,
+    policyId: number,
+    policyType: PolicyType,
+    memo: number[]
+  ): Promise<TransactionInstruction> {
+    const user = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(user, tokenMint);
+    const paymentPolicy = this.getPaymentPolicyPda(userPaymentPda, policyId);
     const accounts = {
       user: user,
       userPayment: userPaymentPda,
@@ -169,6 +546,7 @@ export class Tributary {
       tokenMint: tokenMint,
       gateway: gateway,
       paymentPolicy: paymentPolicy.address,
+      config: getConfigPda(this.programId).address,
       systemProgram: SystemProgram.programId,
     };
     return await this.program.methods
@@ -188,14 +566,22 @@ export class Tributary {
     memo: number[],
     startTime?: anchor.BN | null,
     approvalAmount?: anchor.BN,
-    executeImmediately?: boolean
+    executeImmediately?: boolean,
+    maxTotalAmount?: anchor.BN | null,
+    endTimestamp?: anchor.BN | null
   ): Promise<TransactionInstruction[]> {
     const user = this.provider.publicKey;
     const { address: userPaymentPda } = this.getUserPaymentPda(user, tokenMint);
 
     const instructions: TransactionInstruction[] = [];
 
-    const ownerTokenAccount = getAssociatedTokenAddressSync(tokenMint, user);
+    const tokenProgram = await this.getTokenProgramId(tokenMint);
+    const ownerTokenAccount = getAssociatedTokenAddressSync(
+      tokenMint,
+      user,
+      false,
+      tokenProgram
+    );
     const accountInfo = await this.connection.getAccountInfo(ownerTokenAccount);
 
     if (!accountInfo) {
@@ -204,7 +590,7 @@ export class Tributary {
         ownerTokenAccount,
         user,
         tokenMint,
-        TOKEN_PROGRAM_ID,
+        tokenProgram,
         ASSOCIATED_TOKEN_PROGRAM_ID
       );
       instructions.push(createAtaIx);
@@ -223,7 +609,9 @@ export class Tributary {
     // Determine policy ID
     let policyId: number = 1;
     if (userPayment) {
-      policyId = userPayment.activePoliciesCount + 1;
+      policyId =
+        Math.max(userPayment.lastPolicyId, userPayment.activePoliciesCount) +
+        1;
     }
 
     // Build policy type
@@ -236,7 +624,9 @@ export class Tributary {
         maxRenewals: maxRenewals,
         paymentFrequency: paymentFrequency,
         nextPaymentDue: nextPaymentDue,
-        padding: new Array(97).fill(0),
+        maxTotalAmount: maxTotalAmount ?? new anchor.BN(0),
+        endTimestamp: endTimestamp ?? new anchor.BN(0),
+        padding: new Array(81).fill(0),
       },
     };
 
@@ -249,6 +639,7 @@ export class Tributary {
       tokenMint: tokenMint,
       gateway: gateway,
       paymentPolicy: paymentPolicyPda.address,
+      config: getConfigPda(this.programId).address,
       systemProgram: SystemProgram.programId,
     };
 
@@ -260,7 +651,8 @@ export class Tributary {
     instructions.push(createPaymentPolicyIx);
 
     if (approvalAmount) {
-      const paymentsDelegatePda = this.getPaymentsDelegatePda().address;
+      const paymentsDelegatePda =
+        this.getPaymentsDelegatePda(userPaymentPda).address;
       let needsApproval = false;
 
       const tokenAccountInfo = await this.connection.getParsedAccountInfo(
@@ -291,7 +683,7 @@ export class Tributary {
           user,
           BigInt(approvalAmount.toString()),
           [],
-          TOKEN_PROGRAM_ID
+          tokenProgram
         );
         instructions.push(approveIx);
       }
@@ -316,7 +708,8 @@ export class Tributary {
     recipient?: PublicKey,
     tokenMint?: PublicKey,
     gateway?: PublicKey,
-    user?: PublicKey
+    user?: PublicKey,
+    usageAmount?: anchor.BN | null
   ): Promise<TransactionInstruction[]> {
     const instructions: TransactionInstruction[] = [];
     const authority = this.provider.publicKey;
@@ -382,12 +775,20 @@ export class Tributary {
       _user,
       _tokenMint
     );
-    const tokenAccount = getAssociatedTokenAddressSync(_tokenMint, _user);
+    const tokenProgram = await this.getTokenProgramId(_tokenMint);
+    const tokenAccount = getAssociatedTokenAddressSync(
+      _tokenMint,
+      _user,
+      false,
+      tokenProgram
+    );
 
     // Payment Recipient ATA
     const recipientTokenAccount = getAssociatedTokenAddressSync(
       _tokenMint,
-      _recipient
+      _recipient,
+      false,
+      tokenProgram
     );
     const recipientAccountInfo = await this.connection.getAccountInfo(
       recipientTokenAccount
@@ -398,7 +799,7 @@ export class Tributary {
         recipientTokenAccount,
         _recipient,
         _tokenMint,
-        TOKEN_PROGRAM_ID,
+        tokenProgram,
         ASSOCIATED_TOKEN_PROGRAM_ID
       );
       instructions.push(createAtaIx);
@@ -407,7 +808,9 @@ export class Tributary {
     // Gateway Fee account ATA
     const gatewayFeeAccount = getAssociatedTokenAddressSync(
       _tokenMint,
-      gatewayAccount!.feeRecipient
+      gatewayAccount!.feeRecipient,
+      false,
+      tokenProgram
     );
     const gatewayFeeAccountInfo = await this.connection.getAccountInfo(
       gatewayFeeAccount
@@ -418,7 +821,7 @@ export class Tributary {
         gatewayFeeAccount,
         gatewayAccount!.feeRecipient,
         _tokenMint,
-        TOKEN_PROGRAM_ID,
+        tokenProgram,
         ASSOCIATED_TOKEN_PROGRAM_ID
       );
       instructions.push(createAtaIx);
@@ -427,7 +830,9 @@ export class Tributary {
     // Protocol Fee account ATA
     const protocolFeeAccount = getAssociatedTokenAddressSync(
       _tokenMint,
-      config!.feeRecipient
+      config!.feeRecipient,
+      false,
+      tokenProgram
     );
     const protocolFeeAccountInfo = await this.connection.getAccountInfo(
       protocolFeeAccount
@@ -438,60 +843,272 @@ export class Tributary {
         protocolFeeAccount,
         config!.feeRecipient,
         _tokenMint,
-        TOKEN_PROGRAM_ID,
+        tokenProgram,
         ASSOCIATED_TOKEN_PROGRAM_ID
       );
       instructions.push(createAtaIx);
     }
 
+    const paymentsDelegate =
+      this.getPaymentsDelegatePda(userPaymentPda).address;
     const accounts = {
       feePayer: authority,
-      paymentsDelegate: this.getPaymentsDelegatePda().address,
-      paymentPolicy: paymentPolicyPda,
-      userPayment: userPaymentPda,
-      gateway: _gateway,
-      config: configPda,
-      userTokenAccount: tokenAccount,
-      recipientTokenAccount,
-      gatewayFeeAccount: gatewayFeeAccount,
-      protocolFeeAccount: protocolFeeAccount,
-      tokenProgram: TOKEN_PROGRAM_ID,
+      payment: {
+        paymentsDelegate: paymentsDelegate,
+        paymentPolicy: paymentPolicyPda,
+        userPayment: userPaymentPda,
+        gateway: _gateway,
+        config: configPda,
+        tokenMint: _tokenMint,
+        userTokenAccount: tokenAccount,
+        recipientTokenAccount,
+        gatewayFeeAccount: gatewayFeeAccount,
+        protocolFeeAccount: protocolFeeAccount,
+        tokenProgram,
+      },
     };
+    const remainingAccounts = await this.getTransferHookAccounts(
+      _tokenMint,
+      tokenAccount,
+      recipientTokenAccount,
+      paymentsDelegate,
+      tokenProgram
+    );
     instructions.push(
       await this.program.methods
-        .executePayment()
+        .executePayment(usageAmount ?? null)
         .accountsStrict(accounts)
+        .remainingAccounts(remainingAccounts)
         .instruction()
     );
 
     return instructions;
   }
 
-  // Helper methods to get PDAs
-  getConfigPda() {
-    return getConfigPda(this.programId);
-  }
-
-  getGatewayPda(gatewayAuthority: PublicKey) {
-    return getGatewayPda(gatewayAuthority, this.programId);
-  }
+  // Pay out what a stream accrued, signed by the recipient or the gateway signer
+  // Withdraw `amount` of the accrued stream, or as much as can be collected
+  async withdrawStream(
+    paymentPolicyPda: PublicKey,
+    amount?: anchor.BN | null
+  ): Promise<TransactionInstruction[]> {
+    const instructions: TransactionInstruction[] = [];
+    const authority = this.provider.publicKey;
 
-  getUserPaymentPda(user: PublicKey, tokenMint: PublicKey) {
-    return getUserPaymentPda(user, tokenMint, this.programId);
-  }
+    const paymentPolicy = await this.program.account.paymentPolicy.fetch(
+      paymentPolicyPda
+    );
+    const userPayment = await this.program.account.userPayment.fetch(
+      paymentPolicy.userPayment
+    );
+    const gatewayAccount = await this.program.account.paymentGateway.fetch(
+      paymentPolicy.gateway
+    );
+    const { address: configPda } = getConfigPda(this.programId);
+    const config = await this.program.account.programConfig.fetch(configPda);
 
-  getPaymentPolicyPda(userPayment: PublicKey, policyId: number) {
-    return getPaymentPolicyPda(userPayment, policyId, this.programId);
-  }
+    const tokenMint = userPayment.tokenMint;
+    const tokenProgram = await this.getTokenProgramId(tokenMint);
+
+    // Recipient, gateway fee and protocol fee ATAs, created when missing
+    const [recipientTokenAccount, gatewayFeeAccount, protocolFeeAccount] =
+      await Promise.all(
+        [
+          paymentPolicy.recipient,
+          gatewayAccount.feeRecipient,
+          config.feeRecipient,
+        ].map(async (owner) => {
+          const ata = getAssociatedTokenAddressSync(
+            tokenMint,
+            owner,
+            false,
+            tokenProgram
+          );
+          if (!(await this.connection.getAccountInfo(ata))) {
+            instructions.push(
+              createAssociatedTokenAccountInstruction(
+                authority,
+                ata,
+                owner,
+                tokenMint,
+                tokenProgram,
+                ASSOCIATED_TOKEN_PROGRAM_ID
+              )
+            );
+          }
+          return ata;
+        })
+      );
 
-  getPaymentsDelegatePda() {
-    return getPaymentsDelegatePda(this.programId);
-  }
+    const paymentsDelegate = this.getPaymentsDelegatePda(
+      paymentPolicy.userPayment
+    ).address;
+    const accounts = {
+      authority: authority,
+      payment: {
+        paymentsDelegate: paymentsDelegate,
+        paymentPolicy: paymentPolicyPda,
+        userPayment: paymentPolicy.userPayment,
+        gateway: paymentPolicy.gateway,
+        config: configPda,
+        tokenMint: tokenMint,
+        userTokenAccount: userPayment.tokenAccount,
+        recipientTokenAccount,
+        gatewayFeeAccount,
+        protocolFeeAccount,
+        tokenProgram,
+      },
+    };
+    const remainingAccounts = await this.getTransferHookAccounts(
+      tokenMint,
+      userPayment.tokenAccount,
+      recipientTokenAccount,
+      paymentsDelegate,
+      tokenProgram
+    );
+    instructions.push(
+      await this.program.methods
+        .withdrawStream(amount ?? null)
+        .accountsStrict(accounts)
+        .remainingAccounts(remainingAccounts)
+        .instruction()
+    );
+
+    return instructions;
+  }
+
+  async stopStream(
+    tokenMint: PublicKey,
+    policyId: number
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+    };
+
+    return await this.program.methods
+      .stopStream(policyId)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async reportFailedPayment(
+    paymentPolicyPda: PublicKey
+  ): Promise<TransactionInstruction> {
+    const signer = this.provider.publicKey;
+
+    const paymentPolicy = await this.program.account.paymentPolicy.fetch(
+      paymentPolicyPda
+    );
+    const userPayment = await this.program.account.userPayment.fetch(
+      paymentPolicy.userPayment
+    );
+    const { address: paymentsDelegate } = this.getPaymentsDelegatePda(
+      paymentPolicy.userPayment
+    );
+    const { address: configPda } = getConfigPda(this.programId);
+
+    const accounts = {
+      signer: signer,
+      paymentPolicy: paymentPolicyPda,
+      userPayment: paymentPolicy.userPayment,
+      gateway: paymentPolicy.gateway,
+      config: configPda,
+      paymentsDelegate: paymentsDelegate,
+      userTokenAccount: userPayment.tokenAccount,
+    };
+
+    return await this.program.methods
+      .reportFailedPayment()
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  // Resolve the token program (legacy SPL Token or Token-2022) that owns a mint
+  async getTokenProgramId(tokenMint: PublicKey): Promise<PublicKey> {
+    const mintInfo = await this.connection.getAccountInfo(tokenMint);
+    if (mintInfo && mintInfo.owner.equals(TOKEN_2022_PROGRAM_ID)) {
+      return TOKEN_2022_PROGRAM_ID;
+    }
+    return TOKEN_PROGRAM_ID;
+  }
+
+  // Resolve the extra accounts a Token-2022 transfer hook needs, if the mint has one
+  async getTransferHookAccounts(
+    tokenMint: PublicKey,
+    source: PublicKey,
+    destination: PublicKey,
+    authority: PublicKey,
+    tokenProgram: PublicKey
+  ): Promise<anchor.web3.AccountMeta[]> {
+    if (!tokenProgram.equals(TOKEN_2022_PROGRAM_ID)) {
+      return [];
+    }
+    const mint = await getMint(
+      this.connection,
+      tokenMint,
+      "confirmed",
+      tokenProgram
+    );
+    if (!getTransferHook(mint)) {
+      return [];
+    }
+    // Let spl-token resolve the hook accounts and strip the four transfer accounts
+    const transferIx = await createTransferCheckedWithTransferHookInstruction(
+      this.connection,
+      source,
+      tokenMint,
+      destination,
+      authority,
+      BigInt(0),
+      mint.decimals,
+      [],
+      "confirmed",
+      tokenProgram
+    );
+    return transferIx.keys.slice(4);
+  }
+
+  // Helper methods to get PDAs
+  getConfigPda() {
+    return getConfigPda(this.programId);
+  }
+
+  getGatewayPda(gatewayAuthority: PublicKey) {
+    return getGatewayPda(gatewayAuthority, this.programId);
+  }
+
+  getUserPaymentPda(user: PublicKey, tokenMint: PublicKey) {
+    return getUserPaymentPda(user, tokenMint, this.programId);
+  }
+
+  getPaymentPolicyPda(userPayment: PublicKey, policyId: number) {
+    return getPaymentPolicyPda(userPayment, policyId, this.programId);
+  }
+
+  getPaymentsDelegatePda(userPayment: PublicKey) {
+    return getPaymentsDelegatePda(userPayment, this.programId);
+  }
+
+  getLegacyPaymentsDelegatePda() {
+    return getLegacyPaymentsDelegatePda(this.programId);
+  }
 
   async changePaymentPolicyStatus(
     tokenMint: PublicKey,
     policyId: number,
-    newStatus: { active: {} } | { paused: {} }
+    newStatus: { active: {} } | { paused: {} } | { cancelled: {} }
   ): Promise<TransactionInstruction> {
     const owner = this.provider.publicKey;
     const { address: userPaymentPda } = this.getUserPaymentPda(
@@ -508,6 +1125,7 @@ export class Tributary {
       userPayment: userPaymentPda,
       tokenMint: tokenMint,
       paymentPolicy: paymentPolicyPda,
+      config: getConfigPda(this.programId).address,
     };
 
     return await this.program.methods
@@ -516,6 +1134,235 @@ export class Tributary {
       .instruction();
   }
 
+  async changePolicyGateway(
+    tokenMint: PublicKey,
+    policyId: number,
+    newGateway: PublicKey
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+      newGateway: newGateway,
+      config: getConfigPda(this.programId).address,
+    };
+
+    return await this.program.methods
+      .changePolicyGateway(policyId)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async setMilestoneApprovalMode(
+    tokenMint: PublicKey,
+    policyId: number,
+    approvalRequired: boolean
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+    };
+
+    return await this.program.methods
+      .setMilestoneApprovalMode(policyId, approvalRequired)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async setPolicyCatchUp(
+    tokenMint: PublicKey,
+    policyId: number,
+    catchUp: boolean
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+    };
+
+    return await this.program.methods
+      .setPolicyCatchUp(policyId, catchUp)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async setAutoRenew(
+    tokenMint: PublicKey,
+    policyId: number,
+    autoRenew: boolean
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+    };
+
+    return await this.program.methods
+      .setAutoRenew(policyId, autoRenew)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async approveMilestone(
+    tokenMint: PublicKey,
+    policyId: number,
+    milestoneIndex: number
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+    };
+
+    return await this.program.methods
+      .approveMilestone(policyId, milestoneIndex)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async topUpPolicyAllowance(
+    tokenMint: PublicKey,
+    policyId: number,
+    amount: anchor.BN
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+    };
+
+    return await this.program.methods
+      .topUpPolicyAllowance(policyId, amount)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  // Move an approval granted to the legacy program-wide delegate to the user's own delegate
+  async migrateDelegate(tokenMint: PublicKey): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const userPayment = await this.program.account.userPayment.fetch(
+      userPaymentPda
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      userTokenAccount: userPayment.tokenAccount,
+      legacyDelegate: this.getLegacyPaymentsDelegatePda().address,
+      paymentsDelegate: this.getPaymentsDelegatePda(userPaymentPda).address,
+      tokenProgram: await this.getTokenProgramId(tokenMint),
+    };
+
+    return await this.program.methods
+      .migrateDelegate()
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
+  async acceptPolicyFees(
+    tokenMint: PublicKey,
+    policyId: number
+  ): Promise<TransactionInstruction> {
+    const owner = this.provider.publicKey;
+    const { address: userPaymentPda } = this.getUserPaymentPda(
+      owner,
+      tokenMint
+    );
+    const { address: paymentPolicyPda } = this.getPaymentPolicyPda(
+      userPaymentPda,
+      policyId
+    );
+    const paymentPolicy = await this.program.account.paymentPolicy.fetch(
+      paymentPolicyPda
+    );
+
+    const accounts = {
+      owner: owner,
+      userPayment: userPaymentPda,
+      tokenMint: tokenMint,
+      paymentPolicy: paymentPolicyPda,
+      gateway: paymentPolicy.gateway,
+      config: getConfigPda(this.programId).address,
+    };
+
+    return await this.program.methods
+      .acceptPolicyFees(policyId)
+      .accountsStrict(accounts)
+      .instruction();
+  }
+
   async deletePaymentPolicy(
     tokenMint: PublicKey,
     policyId: number
@@ -582,6 +1429,22 @@ export class Tributary {
       .instruction();
   }
 
+  async setGatewayDunning(
+    maxPaymentRetries: number,
+    retryWindowSeconds: number
+  ): Promise<TransactionInstruction> {
+    const authority = this.provider.publicKey;
+    const { address: gatewayPda } = this.getGatewayPda(authority);
+
+    return await this.program.methods
+      .setGatewayDunning(maxPaymentRetries, new anchor.BN(retryWindowSeconds))
+      .accountsStrict({
+        authority,
+        gateway: gatewayPda,
+      })
+      .instruction();
+  }
+
   // Query methods
   async getAllPaymentGateway(): Promise<
     Array<{ publicKey: PublicKey; account: PaymentGateway }>
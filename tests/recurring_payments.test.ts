@@ -6,6 +6,7 @@ import {
   LAMPORTS_PER_SOL,
   Commitment,
   Transaction,
+  TransactionInstruction,
   sendAndConfirmTransaction,
 } from "@solana/web3.js";
 import {
@@ -14,9 +15,17 @@ import {
   createAssociatedTokenAccount,
   mintTo,
   approve,
+  revoke,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createInitializeTransferHookInstruction,
+  ExtensionType,
+  getMintLen,
+  TOKEN_2022_PROGRAM_ID,
 } from "@solana/spl-token";
 import { ComputeBudgetProgram } from "@solana/web3.js";
 import { RecurringPayments } from "../target/types/recurring_payments";
+import { DummyTransferHook } from "../target/types/dummy_transfer_hook";
 import { PaymentFrequency, RecurringPaymentsSDK } from "../sdk/src";
 import assert from "assert";
 
@@ -64,6 +73,99 @@ describe("Recurring Payments", () => {
     });
   }
 
+  // A distinct compute limit keeps repeated identical calls from being deduplicated
+  let computeUnitNonce = 0;
+  async function sendUnique(
+    instructions: TransactionInstruction[],
+    signers: Keypair[]
+  ): Promise<string> {
+    const transaction = new Transaction().add(
+      ComputeBudgetProgram.setComputeUnitLimit({
+        units: 300000 + computeUnitNonce++,
+      }),
+      ...instructions
+    );
+    return sendAndConfirmTransaction(connection, transaction, signers, {
+      commitment: "processed" as Commitment,
+    });
+  }
+
+  interface SubscriptionOptions {
+    memo: string;
+    amount: number;
+    frequency: PaymentFrequency;
+    startTime: anchor.BN | null;
+    maxRenewals?: number | null;
+    approvalAmount?: number;
+    maxTotalAmount?: anchor.BN | null;
+  }
+
+  // Creates a fresh mint with a funded user token account and, when asked,
+  // an auto-renewing subscription as the user's first policy for it
+  async function createFixture(
+    balance: number,
+    subscription?: SubscriptionOptions
+  ): Promise<{
+    mint: PublicKey;
+    userTokenAccount: PublicKey;
+    userPayment: PublicKey;
+    policy: PublicKey;
+  }> {
+    const mint = await createMint(
+      connection,
+      mintAuthority,
+      mintAuthority.publicKey,
+      null,
+      6
+    );
+    const userTokenAccount = await createAssociatedTokenAccount(
+      connection,
+      user,
+      mint,
+      user.publicKey
+    );
+    await mintTo(
+      connection,
+      mintAuthority,
+      mint,
+      userTokenAccount,
+      mintAuthority,
+      balance
+    );
+    const { address: userPayment } = sdk.getUserPaymentPda(
+      user.publicKey,
+      mint
+    );
+    const { address: policy } = sdk.getPaymentPolicyPda(userPayment, 1);
+
+    await sdk.updateWallet(new anchor.Wallet(user));
+    if (subscription) {
+      const memo = new Uint8Array(64).fill(0);
+      Buffer.from(subscription.memo).copy(memo);
+      const ixs = await sdk.createSubscriptionInstruction(
+        mint,
+        recipient.publicKey,
+        gatewayPDA,
+        new anchor.BN(subscription.amount),
+        true,
+        subscription.maxRenewals ?? null,
+        subscription.frequency,
+        Array.from(memo),
+        subscription.startTime,
+        new anchor.BN(subscription.approvalAmount ?? 1000000),
+        false,
+        subscription.maxTotalAmount ?? null,
+        null
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...ixs),
+        [user]
+      );
+    }
+    return { mint, userTokenAccount, userPayment, policy };
+  }
+
   beforeAll(async () => {
     // Create Solana Kite connection
     connection = provider.connection;
@@ -150,9 +252,9 @@ describe("Recurring Payments", () => {
       program.programId
     );
 
-    // Derive payments delegate PDA
+    // Derive the user's payments delegate PDA
     [paymentsDelegate] = PublicKey.findProgramAddressSync(
-      [Buffer.from("payments")],
+      [Buffer.from("payments"), userPaymentPDA.toBuffer()],
       program.programId
     );
 
@@ -201,6 +303,7 @@ describe("Recurring Payments", () => {
     expect(configAccount!.feeRecipient).toEqual(admin.publicKey);
     expect(configAccount!.protocolFeeBps).toBe(100);
     expect(configAccount!.maxPoliciesPerUser).toBe(10);
+    expect(configAccount!.maxGatewayFeeBps).toBe(1000);
     expect(configAccount!.emergencyPause).toBe(false);
     expect(configAccount!.bump).toBe(configBump);
   });
@@ -881,4 +984,1858 @@ describe("Recurring Payments", () => {
     expect(updatedGateway!.signer).toEqual(newSigner.publicKey);
     expect(updatedGateway!.authority).toEqual(gatewayAuthority.publicKey); // authority should remain unchanged
   });
+
+  test("Update program config", async () => {
+    await sdk.updateWallet(new anchor.Wallet(admin));
+
+    // Out of range protocol fee is rejected
+    try {
+      const ix = await sdk.updateProgramConfig(
+        5000,
+        admin.publicKey,
+        1000,
+        10
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(ix),
+        [admin],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected config update with excessive fee to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("InvalidFeeBps");
+    }
+
+    // Only the admin can update the config
+    await sdk.updateWallet(new anchor.Wallet(user));
+    try {
+      const ix = await sdk.updateProgramConfig(
+        50,
+        user.publicKey,
+        1000,
+        10
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(ix),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected config update by non-admin to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Unauthorized");
+    }
+
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const updateIx = await sdk.updateProgramConfig(
+      50,
+      admin.publicKey,
+      1000,
+      20
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(updateIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    const configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.protocolFeeBps).toBe(50);
+    expect(configAccount!.feeRecipient).toEqual(admin.publicKey);
+    expect(configAccount!.maxPoliciesPerUser).toBe(20);
+    expect(configAccount!.maxGatewayFeeBps).toBe(1000);
+  });
+
+  test("Emergency pause - guardian can pause but not unpause", async () => {
+    const guardian = Keypair.generate();
+    await fund(guardian.publicKey, 1);
+
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const setGuardianIx = await sdk.setGuardian(guardian.publicKey);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(setGuardianIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    let configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.guardian).toEqual(guardian.publicKey);
+
+    // Guardian pauses the program
+    await sdk.updateWallet(new anchor.Wallet(guardian));
+    const pauseIx = await sdk.setEmergencyPause(true);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(pauseIx),
+      [guardian],
+      { commitment: "processed" as Commitment }
+    );
+
+    configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.emergencyPause).toBe(true);
+
+    // Guardian cannot unpause
+    try {
+      const unpauseIx = await sdk.setEmergencyPause(false);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(unpauseIx),
+        [guardian],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected guardian unpause to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Unauthorized");
+    }
+
+    // Payments are blocked while paused
+    await sdk.updateWallet(new anchor.Wallet(gatewayAuthority));
+    try {
+      const executePaymentIxs = await sdk.executePayment(paymentPolicyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executePaymentIxs),
+        [gatewayAuthority],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected payment execution to fail while paused");
+    } catch (error: any) {
+      expect(error.message).toContain("ProgramPaused");
+    }
+
+    // Admin unpauses
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const unpauseIx = await sdk.setEmergencyPause(false);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(unpauseIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.emergencyPause).toBe(false);
+  });
+
+  test("Two-step admin transfer", async () => {
+    const newAdmin = Keypair.generate();
+    await fund(newAdmin.publicKey, 1);
+
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const proposeIx = await sdk.proposeAdmin(newAdmin.publicKey);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(proposeIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    let configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.admin).toEqual(admin.publicKey);
+    expect(configAccount!.pendingAdmin).toEqual(newAdmin.publicKey);
+
+    // Only the proposed key can accept
+    await sdk.updateWallet(new anchor.Wallet(user));
+    try {
+      const acceptIx = await sdk.acceptAdmin();
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(acceptIx),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected accept by wrong key to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Unauthorized");
+    }
+
+    await sdk.updateWallet(new anchor.Wallet(newAdmin));
+    const acceptIx = await sdk.acceptAdmin();
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(acceptIx),
+      [newAdmin],
+      { commitment: "processed" as Commitment }
+    );
+
+    configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.admin).toEqual(newAdmin.publicKey);
+    expect(configAccount!.pendingAdmin).toEqual(PublicKey.default);
+
+    // Hand the role back so later tests keep using the original admin
+    const proposeBackIx = await sdk.proposeAdmin(admin.publicKey);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(proposeBackIx),
+      [newAdmin],
+      { commitment: "processed" as Commitment }
+    );
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const acceptBackIx = await sdk.acceptAdmin();
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(acceptBackIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.admin).toEqual(admin.publicKey);
+  });
+
+  test("Max policies per user is enforced and tracks paused policies", async () => {
+    const userPayment = await sdk.getUserPayment(userPaymentPDA);
+    const activeCount = userPayment!.activePoliciesCount;
+
+    // Cap the config at the current number of active policies
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const capIx = await sdk.updateProgramConfig(
+      50,
+      admin.publicKey,
+      1000,
+      activeCount
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(capIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("over the cap").copy(memo);
+
+    await sdk.updateWallet(new anchor.Wallet(user));
+    try {
+      const createIx = await sdk.createPaymentPolicy(
+        tokenMint,
+        recipient.publicKey,
+        gatewayPDA,
+        new anchor.BN(1000),
+        true,
+        null,
+        { daily: {} },
+        Array.from(memo),
+        null
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(createIx),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected policy creation above the cap to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("MaxPoliciesReached");
+    }
+
+    // Pausing a policy frees up a slot
+    const pauseIx = await sdk.changePaymentPolicyStatus(tokenMint, 4, {
+      paused: {},
+    });
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(pauseIx),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+    let updatedUserPayment = await sdk.getUserPayment(userPaymentPDA);
+    expect(updatedUserPayment!.activePoliciesCount).toBe(activeCount - 1);
+
+    // Resuming counts against the cap again
+    const resumeIx = await sdk.changePaymentPolicyStatus(tokenMint, 4, {
+      active: {},
+    });
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(resumeIx),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+    updatedUserPayment = await sdk.getUserPayment(userPaymentPDA);
+    expect(updatedUserPayment!.activePoliciesCount).toBe(activeCount);
+
+    // Restore the cap
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const restoreIx = await sdk.updateProgramConfig(
+      50,
+      admin.publicKey,
+      1000,
+      20
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(restoreIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+  });
+
+  test("Gateway fee above the configured maximum is rejected", async () => {
+    const otherAuthority = Keypair.generate();
+
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    try {
+      const createGatewayIx = await sdk.createPaymentGateway(
+        otherAuthority.publicKey,
+        9950,
+        feeRecipient.publicKey,
+        "greedy gateway",
+        "https://example.com"
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(createGatewayIx),
+        [admin],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected gateway creation with excessive fee to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("GatewayFeeTooHigh");
+    }
+  });
+
+  test("The gateway fee cap cannot be cleared", async () => {
+    // 0 is what configs created before the cap existed hold, it means the default
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    try {
+      const unsetIx = await sdk.updateProgramConfig(50, admin.publicKey, 0, 20);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(unsetIx),
+        [admin],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected a gateway fee cap of 0 to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain("InvalidFeeBps");
+    }
+
+    const configAccount = await sdk.getProgramConfig(configPDA);
+    expect(configAccount!.maxGatewayFeeBps).toBe(1000);
+  });
+
+  test("Update payment gateway", async () => {
+    await sdk.updateWallet(new anchor.Wallet(gatewayAuthority));
+
+    // Fee bounds apply to updates as well
+    try {
+      const ix = await sdk.updatePaymentGateway(
+        9950,
+        feeRecipient.publicKey,
+        "custom gateway",
+        "https://example.com"
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(ix),
+        [gatewayAuthority],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected gateway update with excessive fee to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("GatewayFeeTooHigh");
+    }
+
+    const updateIx = await sdk.updatePaymentGateway(
+      300,
+      feeRecipient.publicKey,
+      "renamed gateway",
+      "https://example.org"
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(updateIx),
+      [gatewayAuthority],
+      { commitment: "processed" as Commitment }
+    );
+
+    const gatewayAccount = await sdk.getPaymentGateway(gatewayPDA);
+    expect(gatewayAccount!.gatewayFeeBps).toBe(300);
+    expect(gatewayAccount!.feeRecipient).toEqual(feeRecipient.publicKey);
+
+    const nameBuffer = Buffer.from(gatewayAccount!.name);
+    expect(
+      nameBuffer.subarray(0, nameBuffer.indexOf(0)).toString("utf-8")
+    ).toBe("renamed gateway");
+    const urlBuffer = Buffer.from(gatewayAccount!.url);
+    expect(urlBuffer.subarray(0, urlBuffer.indexOf(0)).toString("utf-8")).toBe(
+      "https://example.org"
+    );
+  });
+
+  test("Fee increases only apply after the subscriber accepts them", async () => {
+    const policyId = 4;
+    const [policyPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("payment_policy"),
+        userPaymentPDA.toBuffer(),
+        new anchor.BN(policyId).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+
+    // The policy keeps the gateway fee it was created with
+    let policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.gatewayFeeBps).toBe(250);
+    expect(policy!.hasFeeSnapshot).toBe(true);
+
+    const gatewayAccount = await sdk.getPaymentGateway(gatewayPDA);
+    expect(gatewayAccount!.gatewayFeeBps).toBe(300);
+
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const acceptIx = await sdk.acceptPolicyFees(tokenMint, policyId);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(acceptIx),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.gatewayFeeBps).toBe(300);
+    expect(policy!.protocolFeeBps).toBe(50);
+  });
+
+  test("Suspend and reinstate a payment gateway", async () => {
+    // Admin suspends the gateway
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const suspendIx = await sdk.setGatewayActive(gatewayPDA, false);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(suspendIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    let gatewayAccount = await sdk.getPaymentGateway(gatewayPDA);
+    expect(gatewayAccount!.isActive).toBe(false);
+    expect(gatewayAccount!.suspendedByAdmin).toBe(true);
+
+    // Payments through a suspended gateway are rejected
+    await sdk.updateWallet(new anchor.Wallet(gatewayAuthority));
+    try {
+      const executePaymentIxs = await sdk.executePayment(paymentPolicyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executePaymentIxs),
+        [gatewayAuthority],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected payment through suspended gateway to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("GatewayInactive");
+    }
+
+    // The operator cannot lift an admin suspension
+    try {
+      const reinstateIx = await sdk.setGatewayActive(gatewayPDA, true);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(reinstateIx),
+        [gatewayAuthority],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected operator reinstatement to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Unauthorized");
+    }
+
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const reinstateIx = await sdk.setGatewayActive(gatewayPDA, true);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(reinstateIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    gatewayAccount = await sdk.getPaymentGateway(gatewayPDA);
+    expect(gatewayAccount!.isActive).toBe(true);
+    expect(gatewayAccount!.suspendedByAdmin).toBe(false);
+  });
+
+  test("Move a payment policy to another gateway", async () => {
+    const policyId = 4;
+    const [policyPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("payment_policy"),
+        userPaymentPDA.toBuffer(),
+        new anchor.BN(policyId).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+
+    // Create a second gateway to move to
+    const secondAuthority = Keypair.generate();
+    await fund(secondAuthority.publicKey, 1);
+    const [secondGatewayPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("gateway"), secondAuthority.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const createGatewayIx = await sdk.createPaymentGateway(
+      secondAuthority.publicKey,
+      100,
+      feeRecipient.publicKey,
+      "second gateway",
+      "https://example.net"
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createGatewayIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    const policyBefore = await sdk.getPaymentPolicy(policyPDA);
+
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const moveIx = await sdk.changePolicyGateway(
+      tokenMint,
+      policyId,
+      secondGatewayPDA
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(moveIx),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    const policyAfter = await sdk.getPaymentPolicy(policyPDA);
+    expect(policyAfter!.gateway).toEqual(secondGatewayPDA);
+    expect(policyAfter!.gatewayFeeBps).toBe(100);
+    expect(policyAfter!.totalPaid.toNumber()).toBe(
+      policyBefore!.totalPaid.toNumber()
+    );
+    expect(policyAfter!.paymentCount).toBe(policyBefore!.paymentCount);
+    expect(
+      policyAfter!.policyType.subscription.nextPaymentDue.toNumber()
+    ).toBe(policyBefore!.policyType.subscription.nextPaymentDue.toNumber());
+  });
+
+  test("Retire a gateway and rebind its policies to the successor", async () => {
+    const policyId = 4;
+    const [policyPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("payment_policy"),
+        userPaymentPDA.toBuffer(),
+        new anchor.BN(policyId).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+    const retiredGatewayPDA = (await sdk.getPaymentPolicy(policyPDA))!.gateway;
+    expect(retiredGatewayPDA).not.toEqual(gatewayPDA);
+
+    // Create an interim successor that is later retired as well
+    const interimAuthority = Keypair.generate();
+    await fund(interimAuthority.publicKey, 1);
+    const { address: interimGatewayPDA } = sdk.getGatewayPda(
+      interimAuthority.publicKey
+    );
+    await sdk.updateWallet(new anchor.Wallet(admin));
+    const createGatewayIx = await sdk.createPaymentGateway(
+      interimAuthority.publicKey,
+      100,
+      feeRecipient.publicKey,
+      "interim gateway",
+      "https://example.org"
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createGatewayIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    const retireIx = await sdk.retirePaymentGateway(
+      retiredGatewayPDA,
+      interimGatewayPDA
+    );
+    const retireInterimIx = await sdk.retirePaymentGateway(
+      interimGatewayPDA,
+      gatewayPDA
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(retireIx, retireInterimIx),
+      [admin],
+      { commitment: "processed" as Commitment }
+    );
+
+    const retiredGateway = await sdk.getPaymentGateway(retiredGatewayPDA);
+    expect(retiredGateway!.isActive).toBe(false);
+    expect(retiredGateway!.successor).toEqual(interimGatewayPDA);
+
+    // The retired gateway must stay around until its policies are rebound
+    try {
+      const deleteIx = await sdk.deletePaymentGateway(retiredGateway!.authority);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(deleteIx),
+        [admin],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected deleting a retired gateway to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("GatewayRetired");
+    }
+
+    // Anyone can crank the rebind, following the successors one gateway at a time
+    const cranker = Keypair.generate();
+    await fund(cranker.publicKey, 1);
+    await sdk.updateWallet(new anchor.Wallet(cranker));
+    for (const expectedGateway of [interimGatewayPDA, gatewayPDA]) {
+      const rebindIx = await sdk.rebindPolicy(policyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(rebindIx),
+        [cranker],
+        { commitment: "processed" as Commitment }
+      );
+      const policy = await sdk.getPaymentPolicy(policyPDA);
+      expect(policy!.gateway).toEqual(expectedGateway);
+    }
+  });
+
+  test("Execute payment with a Token-2022 transfer fee mint", async () => {
+    // Create a Token-2022 mint that withholds 1% on every transfer
+    const mint2022 = Keypair.generate();
+    const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+    const mintLamports = await connection.getMinimumBalanceForRentExemption(
+      mintLen
+    );
+    const createMintTx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: mintAuthority.publicKey,
+        newAccountPubkey: mint2022.publicKey,
+        space: mintLen,
+        lamports: mintLamports,
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializeTransferFeeConfigInstruction(
+        mint2022.publicKey,
+        mintAuthority.publicKey,
+        mintAuthority.publicKey,
+        100,
+        BigInt(1_000_000_000),
+        TOKEN_2022_PROGRAM_ID
+      ),
+      createInitializeMintInstruction(
+        mint2022.publicKey,
+        6,
+        mintAuthority.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      createMintTx,
+      [mintAuthority, mint2022],
+      { commitment: "processed" as Commitment }
+    );
+
+    const userToken2022Account = await createAssociatedTokenAccount(
+      connection,
+      user,
+      mint2022.publicKey,
+      user.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      connection,
+      mintAuthority,
+      mint2022.publicKey,
+      userToken2022Account,
+      mintAuthority,
+      1000000n,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // User payment, policy and delegate approval for the new mint
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const amount = new anchor.BN(100000);
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("token-2022 subscription").copy(memo);
+    const ixs = await sdk.createSubscriptionInstruction(
+      mint2022.publicKey,
+      recipient.publicKey,
+      gatewayPDA,
+      amount,
+      true,
+      null,
+      { daily: {} },
+      Array.from(memo),
+      new anchor.BN(Math.floor(Date.now() / 1000) - 60),
+      new anchor.BN(1000000),
+      false
+    );
+    await sendAndConfirmTransaction(connection, new Transaction().add(...ixs), [
+      user,
+    ]);
+
+    const { address: userPayment2022 } = sdk.getUserPaymentPda(
+      user.publicKey,
+      mint2022.publicKey
+    );
+    const { address: policyPDA } = sdk.getPaymentPolicyPda(userPayment2022, 1);
+
+    // The subscriber can trigger its own payment
+    const executeIxs = await sdk.executePayment(policyPDA);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(...executeIxs),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    // The user is charged exactly the policy amount
+    const userBalance = await connection.getTokenAccountBalance(
+      userToken2022Account
+    );
+    expect(userBalance.value.amount).toBe("900000");
+
+    // The recipient receives its share minus the 1% withheld by the mint
+    const recipient2022Account = getAssociatedTokenAddressSync(
+      mint2022.publicKey,
+      recipient.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const policy = await sdk.getPaymentPolicy(policyPDA);
+    const gatewayFee = Math.floor((100000 * policy!.gatewayFeeBps) / 10000);
+    const protocolFee = Math.floor((100000 * policy!.protocolFeeBps) / 10000);
+    const recipientShare = 100000 - gatewayFee - protocolFee;
+    const recipientBalance = await connection.getTokenAccountBalance(
+      recipient2022Account
+    );
+    expect(Number(recipientBalance.value.amount)).toBe(
+      recipientShare - Math.ceil(recipientShare / 100)
+    );
+  });
+
+  test("Execute payment with a Token-2022 transfer hook mint", async () => {
+    const hookProgram = anchor.workspace
+      .DummyTransferHook as anchor.Program<DummyTransferHook>;
+
+    // Create a Token-2022 mint whose transfers go through the dummy hook
+    const hookMint = Keypair.generate();
+    const mintLen = getMintLen([ExtensionType.TransferHook]);
+    const mintLamports = await connection.getMinimumBalanceForRentExemption(
+      mintLen
+    );
+    const createMintTx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: mintAuthority.publicKey,
+        newAccountPubkey: hookMint.publicKey,
+        space: mintLen,
+        lamports: mintLamports,
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializeTransferHookInstruction(
+        hookMint.publicKey,
+        mintAuthority.publicKey,
+        hookProgram.programId,
+        TOKEN_2022_PROGRAM_ID
+      ),
+      createInitializeMintInstruction(
+        hookMint.publicKey,
+        6,
+        mintAuthority.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      createMintTx,
+      [mintAuthority, hookMint],
+      { commitment: "processed" as Commitment }
+    );
+
+    await hookProgram.methods
+      .initializeExtraAccountMetaList()
+      .accounts({ payer: mintAuthority.publicKey, mint: hookMint.publicKey })
+      .signers([mintAuthority])
+      .rpc();
+
+    const userHookAccount = await createAssociatedTokenAccount(
+      connection,
+      user,
+      hookMint.publicKey,
+      user.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      connection,
+      mintAuthority,
+      hookMint.publicKey,
+      userHookAccount,
+      mintAuthority,
+      1000000n,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("transfer hook subscription").copy(memo);
+    const ixs = await sdk.createSubscriptionInstruction(
+      hookMint.publicKey,
+      recipient.publicKey,
+      gatewayPDA,
+      new anchor.BN(100000),
+      true,
+      null,
+      { daily: {} },
+      Array.from(memo),
+      new anchor.BN(Math.floor(Date.now() / 1000) - 60),
+      new anchor.BN(1000000),
+      false
+    );
+    await sendAndConfirmTransaction(connection, new Transaction().add(...ixs), [
+      user,
+    ]);
+
+    const { address: userPaymentHook } = sdk.getUserPaymentPda(
+      user.publicKey,
+      hookMint.publicKey
+    );
+    const { address: policyPDA } = sdk.getPaymentPolicyPda(userPaymentHook, 1);
+
+    const executeIxs = await sdk.executePayment(policyPDA);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(...executeIxs),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    const userBalance = await connection.getTokenAccountBalance(
+      userHookAccount
+    );
+    expect(userBalance.value.amount).toBe("900000");
+
+    // The hook ran once per leg: recipient, gateway fee and protocol fee
+    const [counterPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("counter"), hookMint.publicKey.toBuffer()],
+      hookProgram.programId
+    );
+    const counter = await hookProgram.account.transferCounter.fetch(
+      counterPDA
+    );
+    expect(counter.transfers.toNumber()).toBe(3);
+  });
+
+  test("Migrate an approval from the legacy delegate", async () => {
+    const legacyDelegate = sdk.getLegacyPaymentsDelegatePda().address;
+    await approve(
+      connection,
+      user,
+      userTokenAccount,
+      legacyDelegate,
+      user,
+      500000
+    );
+
+    // The legacy delegate can no longer pull funds
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const { address: policyPDA } = sdk.getPaymentPolicyPda(userPaymentPDA, 4);
+    try {
+      const executeIxs = await sdk.executePayment(policyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected execution against the legacy delegate to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("No or incorrect delegate set in ata");
+    }
+
+    const migrateIx = await sdk.migrateDelegate(tokenMint);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(migrateIx),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    const tokenAccountInfo = await connection.getParsedAccountInfo(
+      userTokenAccount
+    );
+    const parsedData = tokenAccountInfo.value?.data as any;
+    expect(parsedData.parsed.info.delegate).toEqual(
+      paymentsDelegate.toString()
+    );
+    expect(parsedData.parsed.info.delegatedAmount.amount).toBe("500000");
+  });
+
+  test("Execute payment fails when the approval does not cover the amount", async () => {
+    // Approve only half of a single payment
+    const { userTokenAccount: userLowAllowanceAccount, policy: policyPDA } =
+      await createFixture(1000000, {
+        memo: "low allowance subscription",
+        amount: 100000,
+        frequency: { daily: {} },
+        startTime: new anchor.BN(Math.floor(Date.now() / 1000) - 60),
+        approvalAmount: 50000,
+      });
+
+    try {
+      const executeIxs = await sdk.executePayment(policyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected execution with a short approval to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Insufficient delegated amount");
+    }
+
+    const balance = await connection.getTokenAccountBalance(
+      userLowAllowanceAccount
+    );
+    expect(balance.value.amount).toBe("1000000");
+  });
+
+  test("Policy allowance caps payments until topped up", async () => {
+    const { mint: budgetMint, policy: policyPDA } = await createFixture(
+      1000000,
+      {
+        memo: "budgeted subscription",
+        amount: 100000,
+        frequency: { daily: {} },
+        startTime: new anchor.BN(Math.floor(Date.now() / 1000) - 60),
+      }
+    );
+
+    // A budget below the payment amount blocks the payment despite the approval
+    const topUpIx = await sdk.topUpPolicyAllowance(
+      budgetMint,
+      1,
+      new anchor.BN(50000)
+    );
+    await sendAndConfirmTransaction(connection, new Transaction().add(topUpIx), [
+      user,
+    ]);
+    try {
+      const executeIxs = await sdk.executePayment(policyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected execution beyond the policy allowance to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Payment policy allowance exhausted");
+    }
+
+    // Topping up lets the payment through and spends the budget
+    const secondTopUpIx = await sdk.topUpPolicyAllowance(
+      budgetMint,
+      1,
+      new anchor.BN(50000)
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(secondTopUpIx),
+      [user]
+    );
+    const executeIxs = await sdk.executePayment(policyPDA);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(...executeIxs),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    const policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.allowanceEnabled).toBe(true);
+    expect(policy!.allowanceRemaining.toNumber()).toBe(0);
+    expect(policy!.paymentCount).toBe(1);
+  });
+
+  test("Subscription completes once its lifetime cap is reached", async () => {
+    // A cap of 150000 leaves room for a single 100000 payment
+    const {
+      mint: cappedMint,
+      userPayment: userPaymentCapped,
+      policy: policyPDA,
+    } = await createFixture(1000000, {
+      memo: "capped subscription",
+      amount: 100000,
+      frequency: { daily: {} },
+      startTime: new anchor.BN(Math.floor(Date.now() / 1000) - 60),
+      maxTotalAmount: new anchor.BN(150000),
+    });
+
+    const executeIxs = await sdk.executePayment(policyPDA);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(...executeIxs),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    const policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.totalPaid.toNumber()).toBe(100000);
+    expect(policy!.status).toEqual({ completed: {} });
+
+    const userPayment = await sdk.getUserPayment(userPaymentCapped);
+    expect(userPayment!.activePoliciesCount).toBe(0);
+
+    // A completed policy cannot be resumed
+    try {
+      const resumeIx = await sdk.changePaymentPolicyStatus(cappedMint, 1, {
+        active: {},
+      });
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(resumeIx),
+        [user]
+      );
+      assert(false, "Expected resuming a completed policy to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Invalid policy status transition");
+    }
+  });
+
+  test("Subscription rejects an end date before the first payment", async () => {
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const memo = new Uint8Array(64).fill(0);
+    const start = Math.floor(Date.now() / 1000);
+    try {
+      const createIx = await sdk.createPaymentPolicy(
+        tokenMint,
+        recipient.publicKey,
+        gatewayPDA,
+        new anchor.BN(10000),
+        false,
+        null,
+        { monthly: {} },
+        Array.from(memo),
+        new anchor.BN(start),
+        null,
+        new anchor.BN(start - 1)
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(createIx),
+        [user]
+      );
+      assert(false, "Expected an end date in the past to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain(
+        "End timestamp must be after the first payment"
+      );
+    }
+  });
+
+  test("Subscription accounts created before end dates and caps still decode", async () => {
+    // Baseline layout: the Subscription variant ended in 97 bytes of padding
+    const data = Buffer.alloc(586);
+    const discriminator = program.idl.accounts!.find(
+      (account) => account.name === "paymentPolicy"
+    )!.discriminator;
+    Buffer.from(discriminator).copy(data, 0);
+    user.publicKey.toBuffer().copy(data, 8);
+    recipient.publicKey.toBuffer().copy(data, 40);
+    gatewayPDA.toBuffer().copy(data, 72);
+    let offset = 104;
+    data.writeUInt8(0, offset++); // PolicyType::Subscription
+    data.writeBigUInt64LE(BigInt(10000), offset); // amount
+    offset += 8;
+    data.writeUInt8(1, offset++); // auto_renew
+    data.writeUInt8(0, offset++); // max_renewals: None
+    data.writeUInt8(2, offset++); // PaymentFrequency::Monthly
+    data.writeBigInt64LE(BigInt(1700000000), offset); // next_payment_due
+    offset += 8 + 97; // padding
+    data.writeUInt8(1, offset++); // PaymentStatus::Paused
+    Buffer.from("legacy").copy(data, offset); // memo
+    offset += 64;
+    data.writeBigUInt64LE(BigInt(30000), offset); // total_paid
+    offset += 8;
+    data.writeUInt32LE(3, offset); // payment_count
+    offset += 4 + 8 + 8;
+    data.writeUInt32LE(7, offset); // policy_id
+
+    const policy = program.coder.accounts.decode("paymentPolicy", data);
+    expect(policy.policyType.subscription.amount.toNumber()).toBe(10000);
+    expect(policy.policyType.subscription.nextPaymentDue.toNumber()).toBe(
+      1700000000
+    );
+    expect(policy.policyType.subscription.maxTotalAmount.toNumber()).toBe(0);
+    expect(policy.policyType.subscription.endTimestamp.toNumber()).toBe(0);
+    expect(policy.status).toEqual({ paused: {} });
+    expect(Buffer.from(policy.memo).subarray(0, 6).toString()).toBe("legacy");
+    expect(policy.totalPaid.toNumber()).toBe(30000);
+    expect(policy.paymentCount).toBe(3);
+    expect(policy.policyId).toBe(7);
+  });
+
+  test("Installment plan pays the remainder last and completes", async () => {
+    const {
+      mint: installmentMint,
+      userTokenAccount: userInstallmentAccount,
+      userPayment: userPaymentInstallment,
+      policy: policyPDA,
+    } = await createFixture(1000000);
+
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("installment plan").copy(memo);
+    const createUserPaymentIx = await sdk.createUserPayment(installmentMint);
+    const createPolicyIx = await sdk.createInstallmentPolicy(
+      installmentMint,
+      recipient.publicKey,
+      gatewayPDA,
+      new anchor.BN(100001),
+      3,
+      { custom: [new anchor.BN(1)] },
+      Array.from(memo)
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createUserPaymentIx, createPolicyIx),
+      [user]
+    );
+    await approve(
+      connection,
+      user,
+      userInstallmentAccount,
+      sdk.getPaymentsDelegatePda(userPaymentInstallment).address,
+      user,
+      1000000
+    );
+
+    for (let i = 0; i < 3; i++) {
+      if (i > 0) {
+        await new Promise((resolve) => setTimeout(resolve, 2000));
+      }
+      const executeIxs = await sdk.executePayment(policyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+    }
+
+    const policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.policyType.installment.installmentsCompleted).toBe(3);
+    expect(policy!.totalPaid.toNumber()).toBe(100001);
+    expect(policy!.status).toEqual({ completed: {} });
+
+    const balance = await connection.getTokenAccountBalance(
+      userInstallmentAccount
+    );
+    expect(balance.value.amount).toBe("899999");
+  });
+
+  test("One-time payment completes once and expires after its grace period", async () => {
+    const {
+      mint: invoiceMint,
+      userTokenAccount: userInvoiceAccount,
+      userPayment: userPaymentInvoice,
+    } = await createFixture(1000000);
+
+    const now = Math.floor(Date.now() / 1000);
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("invoice").copy(memo);
+    const createUserPaymentIx = await sdk.createUserPayment(invoiceMint);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createUserPaymentIx),
+      [user]
+    );
+
+    // Invoice 1 is within its grace period, invoice 2 is long overdue
+    const createDueIx = await sdk.createOneTimePolicy(
+      invoiceMint,
+      recipient.publicKey,
+      gatewayPDA,
+      new anchor.BN(100000),
+      new anchor.BN(now - 60),
+      new anchor.BN(3600),
+      Array.from(memo)
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createDueIx),
+      [user]
+    );
+    const createOverdueIx = await sdk.createOneTimePolicy(
+      invoiceMint,
+      recipient.publicKey,
+      gatewayPDA,
+      new anchor.BN(100000),
+      new anchor.BN(now - 3600),
+      new anchor.BN(60),
+      Array.from(memo)
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createOverdueIx),
+      [user]
+    );
+    await approve(
+      connection,
+      user,
+      userInvoiceAccount,
+      sdk.getPaymentsDelegatePda(userPaymentInvoice).address,
+      user,
+      1000000
+    );
+
+    const executeIxs = await sdk.executePayment(
+      sdk.getPaymentPolicyPda(userPaymentInvoice, 1).address
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(...executeIxs),
+      [user],
+      { commitment: "processed" as Commitment }
+    );
+
+    // An overdue invoice can no longer be collected
+    const { address: overduePDA } = sdk.getPaymentPolicyPda(
+      userPaymentInvoice,
+      2
+    );
+    try {
+      const overdueIxs = await sdk.executePayment(overduePDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...overdueIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected collecting an overdue invoice to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("PolicyExpired");
+    }
+
+    // Anyone can expire it, even after the approval was revoked
+    await revoke(connection, user, userInvoiceAccount, user);
+    const activeBefore = (await sdk.getUserPayment(userPaymentInvoice))!
+      .activePoliciesCount;
+    const cranker = Keypair.generate();
+    await fund(cranker.publicKey, 1);
+    await sdk.updateWallet(new anchor.Wallet(cranker));
+    const expireIx = await sdk.expirePaymentPolicy(overduePDA);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(expireIx),
+      [cranker],
+      { commitment: "processed" as Commitment }
+    );
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const activeAfter = (await sdk.getUserPayment(userPaymentInvoice))!
+      .activePoliciesCount;
+    expect(activeAfter).toBe(activeBefore - 1);
+
+    const paid = await sdk.getPaymentPolicy(
+      sdk.getPaymentPolicyPda(userPaymentInvoice, 1).address
+    );
+    expect(paid!.status).toEqual({ completed: {} });
+    expect(paid!.totalPaid.toNumber()).toBe(100000);
+
+    const expired = await sdk.getPaymentPolicy(
+      sdk.getPaymentPolicyPda(userPaymentInvoice, 2).address
+    );
+    expect(expired!.status).toEqual({ expired: {} });
+    expect(expired!.totalPaid.toNumber()).toBe(0);
+
+    const balance = await connection.getTokenAccountBalance(
+      userInvoiceAccount
+    );
+    expect(balance.value.amount).toBe("900000");
+
+    // A completed invoice cannot be pulled a second time
+    try {
+      const executeIxs = await sdk.executePayment(
+        sdk.getPaymentPolicyPda(userPaymentInvoice, 1).address
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected a second pull of a one-time payment to fail");
+    } catch (error: any) {
+      expect(error.message).toContain("Payment policy is paused");
+    }
+  });
+
+  test("Milestone plan pays approved milestones in order", async () => {
+    const {
+      mint: milestoneMint,
+      userTokenAccount: userMilestoneAccount,
+      userPayment: userPaymentMilestone,
+      policy: policyPDA,
+    } = await createFixture(1000000);
+
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("milestone plan").copy(memo);
+    const createUserPaymentIx = await sdk.createUserPayment(milestoneMint);
+    const createPolicyIx = await sdk.createMilestonePolicy(
+      milestoneMint,
+      recipient.publicKey,
+      gatewayPDA,
+      [new anchor.BN(100000), new anchor.BN(200000)],
+      [new anchor.BN(0), new anchor.BN(0)],
+      Array.from(memo)
+    );
+    const approvalModeIx = await sdk.setMilestoneApprovalMode(
+      milestoneMint,
+      1,
+      true
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createUserPaymentIx, createPolicyIx, approvalModeIx),
+      [user]
+    );
+    await approve(
+      connection,
+      user,
+      userMilestoneAccount,
+      sdk.getPaymentsDelegatePda(userPaymentMilestone).address,
+      user,
+      1000000
+    );
+
+    const execute = async () => {
+      const executeIxs = await sdk.executePayment(policyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+    };
+
+    // The first milestone is not payable before the owner approves it
+    try {
+      await execute();
+      assert(false, "Expected an unapproved milestone to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain("Milestone has not been approved");
+    }
+
+    for (const milestoneIndex of [0, 1]) {
+      const approveIx = await sdk.approveMilestone(
+        milestoneMint,
+        1,
+        milestoneIndex
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(approveIx),
+        [user]
+      );
+      await execute();
+    }
+
+    const policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.milestoneCursor).toBe(2);
+    expect(policy!.totalPaid.toNumber()).toBe(300000);
+    expect(policy!.status).toEqual({ completed: {} });
+  });
+
+  test("Metered policy charges the usage reported by the gateway", async () => {
+    const {
+      mint: meteredMint,
+      userTokenAccount: userMeteredAccount,
+      userPayment: userPaymentMetered,
+      policy: policyPDA,
+    } = await createFixture(1000000);
+
+    // Make the gateway authority the signer again so it can report usage
+    await sdk.updateWallet(new anchor.Wallet(gatewayAuthority));
+    const changeSignerIx = await sdk.changeGatewaySigner(
+      gatewayAuthority.publicKey,
+      gatewayAuthority.publicKey
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(changeSignerIx),
+      [gatewayAuthority]
+    );
+
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("metered plan").copy(memo);
+    const createUserPaymentIx = await sdk.createUserPayment(meteredMint);
+    const createPolicyIx = await sdk.createMeteredPolicy(
+      meteredMint,
+      recipient.publicKey,
+      gatewayPDA,
+      new anchor.BN(50000),
+      { daily: {} },
+      Array.from(memo),
+      new anchor.BN(Math.floor(Date.now() / 1000) - 60)
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createUserPaymentIx, createPolicyIx),
+      [user]
+    );
+    await approve(
+      connection,
+      user,
+      userMeteredAccount,
+      sdk.getPaymentsDelegatePda(userPaymentMetered).address,
+      user,
+      1000000
+    );
+
+    const executeAs = async (signer: Keypair, usage: number) => {
+      await sdk.updateWallet(new anchor.Wallet(signer));
+      const executeIxs = await sdk.executePayment(
+        policyPDA,
+        undefined,
+        undefined,
+        undefined,
+        undefined,
+        new anchor.BN(usage)
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [signer],
+        { commitment: "processed" as Commitment }
+      );
+    };
+
+    // The subscriber cannot report their own usage
+    try {
+      await executeAs(user, 10000);
+      assert(false, "Expected usage reported by the owner to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain(
+        "Usage amount is missing or not allowed for this policy"
+      );
+    }
+
+    // Usage above the per-period cap is rejected
+    try {
+      await executeAs(gatewayAuthority, 60000);
+      assert(false, "Expected usage above the cap to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain(
+        "Usage amount exceeds the maximum per period"
+      );
+    }
+
+    await executeAs(gatewayAuthority, 30000);
+
+    const policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.totalPaid.toNumber()).toBe(30000);
+    expect(policy!.paymentCount).toBe(1);
+
+    const balance = await connection.getTokenAccountBalance(
+      userMeteredAccount
+    );
+    expect(balance.value.amount).toBe("970000");
+  });
+
+  test("Stream accrues per second and completes after stop and withdrawal", async () => {
+    const {
+      mint: streamMint,
+      userTokenAccount: userStreamAccount,
+      userPayment: userPaymentStream,
+      policy: policyPDA,
+    } = await createFixture(1000000);
+
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("stream").copy(memo);
+    const createUserPaymentIx = await sdk.createUserPayment(streamMint);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createUserPaymentIx),
+      [user]
+    );
+
+    // A stream cannot accrue for time before it was created
+    try {
+      const backdatedIx = await sdk.createStreamPolicy(
+        streamMint,
+        recipient.publicKey,
+        gatewayPDA,
+        new anchor.BN(1000),
+        Array.from(memo),
+        new anchor.BN(Math.floor(Date.now() / 1000) - 100)
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(backdatedIx),
+        [user]
+      );
+      assert(false, "Expected a backdated stream to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain("Invalid stream");
+    }
+
+    const createPolicyIx = await sdk.createStreamPolicy(
+      streamMint,
+      recipient.publicKey,
+      gatewayPDA,
+      new anchor.BN(1000),
+      Array.from(memo)
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createPolicyIx),
+      [user]
+    );
+    await approve(
+      connection,
+      user,
+      userStreamAccount,
+      sdk.getPaymentsDelegatePda(userPaymentStream).address,
+      user,
+      1000000
+    );
+
+    let policy = await sdk.getPaymentPolicy(policyPDA);
+    const start = policy!.policyType.stream.startTime.toNumber();
+    const withdraw = async (amount?: anchor.BN) => {
+      await sdk.updateWallet(new anchor.Wallet(recipient));
+      const withdrawIxs = await sdk.withdrawStream(policyPDA, amount);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...withdrawIxs),
+        [recipient],
+        { commitment: "processed" as Commitment }
+      );
+    };
+
+    // Streams are not paid through execute_payment
+    try {
+      const executeIxs = await sdk.executePayment(policyPDA);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected execute_payment on a stream to fail");
+    } catch (error: any) {
+      expect(error.message).toContain(
+        "Operation not supported for this policy type"
+      );
+    }
+
+    // A running stream can only be stopped, pausing would not stop accrual and
+    // cancelling or deleting it would strand what accrued
+    const endAttempts = [
+      sdk.changePaymentPolicyStatus(streamMint, 1, { paused: {} }),
+      sdk.changePaymentPolicyStatus(streamMint, 1, { cancelled: {} }),
+      sdk.deletePaymentPolicy(streamMint, 1),
+    ];
+    for (const endAttempt of endAttempts) {
+      try {
+        const endIx = await endAttempt;
+        await sendAndConfirmTransaction(
+          connection,
+          new Transaction().add(endIx),
+          [user]
+        );
+        assert(false, "Expected ending a running stream to fail");
+      } catch (error: any) {
+        expect(error.message).toContain(
+          "Operation not supported for this policy type"
+        );
+      }
+    }
+
+    // A partial withdrawal pays whole seconds and moves the checkpoint accordingly
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    await withdraw(new anchor.BN(1500));
+    policy = await sdk.getPaymentPolicy(policyPDA);
+    expect(policy!.totalPaid.toNumber()).toBe(1000);
+    expect(policy!.policyType.stream.lastWithdrawnAt.toNumber()).toBe(
+      start + 1
+    );
+
+    // Without an amount the recipient withdraws what accrued so far
+    await withdraw();
+    policy = await sdk.getPaymentPolicy(policyPDA);
+    const withdrawnUntil = policy!.policyType.stream.lastWithdrawnAt.toNumber();
+    expect(withdrawnUntil).toBeGreaterThan(start + 1);
+    expect(policy!.totalPaid.toNumber()).toBe(1000 * (withdrawnUntil - start));
+    expect(policy!.status).toEqual({ active: {} });
+
+    // The payer stops the stream, the remainder stays withdrawable
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await sdk.updateWallet(new anchor.Wallet(user));
+    const stopIx = await sdk.stopStream(streamMint, 1);
+    await sendAndConfirmTransaction(connection, new Transaction().add(stopIx), [
+      user,
+    ]);
+    await withdraw();
+
+    policy = await sdk.getPaymentPolicy(policyPDA);
+    const stoppedAt = policy!.policyType.stream.stoppedAt.toNumber();
+    expect(policy!.totalPaid.toNumber()).toBe(1000 * (stoppedAt - start));
+    expect(policy!.status).toEqual({ completed: {} });
+
+    const balance = await connection.getTokenAccountBalance(
+      userStreamAccount
+    );
+    expect(Number(balance.value.amount)).toBe(
+      1000000 - 1000 * (stoppedAt - start)
+    );
+  });
+
+  test("Missed periods are recorded as arrears or collected with catch-up", async () => {
+    // Both policies have three periods due: 150s, 90s and 30s ago
+    const start = new anchor.BN(Math.floor(Date.now() / 1000) - 150);
+    const { mint: arrearsMint, userPayment: userPaymentArrears } =
+      await createFixture(1000000, {
+        memo: "arrears",
+        amount: 10000,
+        frequency: { custom: [new anchor.BN(60)] },
+        startTime: start,
+      });
+    const memo = new Uint8Array(64).fill(0);
+    Buffer.from("arrears").copy(memo);
+    const createCatchUpIx = await sdk.createPaymentPolicy(
+      arrearsMint,
+      recipient.publicKey,
+      gatewayPDA,
+      new anchor.BN(10000),
+      true,
+      null,
+      { custom: [new anchor.BN(60)] },
+      Array.from(memo),
+      start
+    );
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(createCatchUpIx),
+      [user]
+    );
+    const catchUpIx = await sdk.setPolicyCatchUp(arrearsMint, 2, true);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(catchUpIx),
+      [user]
+    );
+
+    const execute = async (policyId: number) => {
+      const executeIxs = await sdk.executePayment(
+        sdk.getPaymentPolicyPda(userPaymentArrears, policyId).address
+      );
+      await sendUnique(executeIxs, [user]);
+    };
+
+    // Without catch-up one period is paid and two become arrears
+    await execute(1);
+    const skipped = await sdk.getPaymentPolicy(
+      sdk.getPaymentPolicyPda(userPaymentArrears, 1).address
+    );
+    expect(skipped!.periodsDue).toBe(3);
+    expect(skipped!.arrearsPeriods).toBe(2);
+    expect(skipped!.totalPaid.toNumber()).toBe(10000);
+
+    // With catch-up every outstanding period is collected, one per call
+    for (let i = 0; i < 3; i++) {
+      await execute(2);
+    }
+    try {
+      await execute(2);
+      assert(false, "Expected no further period to be due");
+    } catch (error: any) {
+      expect(error.message).toContain("Payment is not yet due");
+    }
+    const caughtUp = await sdk.getPaymentPolicy(
+      sdk.getPaymentPolicyPda(userPaymentArrears, 2).address
+    );
+    expect(caughtUp!.periodsDue).toBe(3);
+    expect(caughtUp!.arrearsPeriods).toBe(0);
+    expect(caughtUp!.totalPaid.toNumber()).toBe(30000);
+  });
+
+  test("An uncollectable policy moves to PastDue and then Suspended", async () => {
+    // Not enough balance to cover a single payment
+    const { policy: dunningPolicyPda } = await createFixture(5000, {
+      memo: "dunning",
+      amount: 10000,
+      frequency: { custom: [new anchor.BN(60)] },
+      startTime: new anchor.BN(Math.floor(Date.now() / 1000) - 10),
+    });
+
+    // Allow a single retry before suspension
+    await sdk.updateWallet(new anchor.Wallet(gatewayAuthority));
+    const dunningIx = await sdk.setGatewayDunning(1, 86400);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(dunningIx),
+      [gatewayAuthority]
+    );
+    const gateway = await sdk.getPaymentGateway(gatewayPDA);
+    expect(gateway!.maxPaymentRetries).toBe(1);
+    expect(gateway!.retryWindowSeconds.toNumber()).toBe(86400);
+
+    const report = async () => {
+      const reportIx = await sdk.reportFailedPayment(dunningPolicyPda);
+      await sendUnique([reportIx], [gatewayAuthority]);
+    };
+
+    await report();
+    const pastDue = await sdk.getPaymentPolicy(dunningPolicyPda);
+    expect(pastDue!.status).toEqual({ pastDue: {} });
+    expect(pastDue!.failedAttempts).toBe(1);
+
+    await report();
+    const suspended = await sdk.getPaymentPolicy(dunningPolicyPda);
+    expect(suspended!.status).toEqual({ suspended: {} });
+    expect(suspended!.failedAttempts).toBe(2);
+
+    // A suspended policy can no longer be reported or executed
+    try {
+      await report();
+      assert(false, "Expected reporting a suspended policy to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain("PolicyPaused");
+    }
+    await sdk.updateWallet(new anchor.Wallet(user));
+    try {
+      const executeIxs = await sdk.executePayment(dunningPolicyPda);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(...executeIxs),
+        [user],
+        { commitment: "processed" as Commitment }
+      );
+      assert(false, "Expected executing a suspended policy to be rejected");
+    } catch (error: any) {
+      expect(error.message).toContain("PolicyPaused");
+    }
+
+    // Restore the default dunning settings of the shared gateway
+    await sdk.updateWallet(new anchor.Wallet(gatewayAuthority));
+    const restoreIx = await sdk.setGatewayDunning(3, 604800);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(restoreIx),
+      [gatewayAuthority]
+    );
+  });
+
+  test("Policy status changes follow the state machine", async () => {
+    const { mint: statusMint, policy: policyPda } = await createFixture(
+      1000000,
+      {
+        memo: "status machine",
+        amount: 10000,
+        frequency: { monthly: {} },
+        startTime: null,
+      }
+    );
+
+    const changeStatus = async (newStatus: any) => {
+      const ix = await sdk.changePaymentPolicyStatus(statusMint, 1, newStatus);
+      await sendUnique([ix], [user]);
+    };
+    const expectRejected = async (newStatus: any) => {
+      try {
+        await changeStatus(newStatus);
+        assert(false, "Expected the status transition to be rejected");
+      } catch (error: any) {
+        expect(error.message).toContain("Invalid policy status transition");
+      }
+    };
+
+    // Dunning and completion states are set by the program only
+    await expectRejected({ pastDue: {} });
+    await expectRejected({ suspended: {} });
+    await expectRejected({ completed: {} });
+
+    // Re-applying the current status is not a transition
+    await expectRejected({ active: {} });
+
+    // Pausing and resuming are allowed
+    await changeStatus({ paused: {} });
+    await expectRejected({ paused: {} });
+    await changeStatus({ active: {} });
+    let policy = await sdk.getPaymentPolicy(policyPda);
+    expect(policy!.status).toEqual({ active: {} });
+
+    // Cancelling is final
+    await changeStatus({ cancelled: {} });
+    policy = await sdk.getPaymentPolicy(policyPda);
+    expect(policy!.status).toEqual({ cancelled: {} });
+    await expectRejected({ active: {} });
+    await expectRejected({ paused: {} });
+  });
+
+  test("Auto-renewing subscriptions start a new term, others complete", async () => {
+    // Terms of two payments, with five periods already due and collected one by one
+    const { mint: renewMint, policy: renewPolicyPda } = await createFixture(
+      1000000,
+      {
+        memo: "auto renew",
+        amount: 10000,
+        frequency: { custom: [new anchor.BN(60)] },
+        startTime: new anchor.BN(Math.floor(Date.now() / 1000) - 250),
+        maxRenewals: 2,
+      }
+    );
+    const catchUpIx = await sdk.setPolicyCatchUp(renewMint, 1, true);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(catchUpIx),
+      [user]
+    );
+
+    const execute = async () => {
+      const executeIxs = await sdk.executePayment(renewPolicyPda);
+      await sendUnique(executeIxs, [user]);
+    };
+
+    // The end of the first term starts a new one
+    await execute();
+    await execute();
+    let policy = await sdk.getPaymentPolicy(renewPolicyPda);
+    expect(policy!.status).toEqual({ active: {} });
+    expect(policy!.renewalCount).toBe(1);
+    expect(policy!.termStartPaymentCount).toBe(2);
+
+    // Without auto-renew the policy completes at the end of the current term
+    const autoRenewIx = await sdk.setAutoRenew(renewMint, 1, false);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(autoRenewIx),
+      [user]
+    );
+    policy = await sdk.getPaymentPolicy(renewPolicyPda);
+    expect(policy!.policyType.subscription.autoRenew).toBe(false);
+
+    await execute();
+    await execute();
+    policy = await sdk.getPaymentPolicy(renewPolicyPda);
+    expect(policy!.status).toEqual({ completed: {} });
+    expect(policy!.paymentCount).toBe(4);
+    expect(policy!.renewalCount).toBe(1);
+    expect(policy!.totalPaid.toNumber()).toBe(40000);
+  });
 });